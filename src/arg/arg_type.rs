@@ -1,6 +1,7 @@
 use std::fmt;
 
 /// Possible argument types.
+#[derive(Debug, Clone, PartialEq)]
 pub enum Type {
     Bool,
     Str,
@@ -8,6 +9,13 @@ pub enum Type {
     Float,
 }
 
+impl Type {
+    /// Check whether the type is numeric (integer or float).
+    pub fn is_numeric(&self) -> bool {
+        matches!(self, Type::Int | Type::Float)
+    }
+}
+
 impl fmt::Display for Type {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{}", match self {
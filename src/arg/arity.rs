@@ -0,0 +1,45 @@
+use std::fmt;
+use crate::arg::Value;
+
+/// Number of command line arguments an argument descriptor takes.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Arity {
+    /// Exactly one value.
+    Single,
+
+    /// At most one value, falling back to the default value when not specified.
+    Optional { default: Value },
+
+    /// Between `min` and `max` values (no upper bound if `max` is `None`) collected in a list.
+    Range { min: usize, max: Option<usize> },
+}
+
+impl Arity {
+    /// Get the minimum number of values.
+    pub fn min(&self) -> usize {
+        match self {
+            Arity::Single => 1,
+            Arity::Optional { default: _ } => 0,
+            Arity::Range { min, max: _ } => *min,
+        }
+    }
+
+    /// Get the maximum number of values (`None` if unbounded).
+    pub fn max(&self) -> Option<usize> {
+        match self {
+            Arity::Single => Some(1),
+            Arity::Optional { default: _ } => Some(1),
+            Arity::Range { min: _, max } => *max,
+        }
+    }
+}
+
+impl fmt::Display for Arity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.min(), self.max()) {
+            (min, Some(max)) if min == max => write!(f, "{}", min),
+            (min, Some(max)) => write!(f, "{} to {}", min, max),
+            (min, None) => write!(f, "at least {}", min),
+        }
+    }
+}
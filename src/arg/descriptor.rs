@@ -1,28 +1,80 @@
-use crate::arg::Type;
+use crate::arg::{Type, Arity, Value};
 
 /// Descriptor for anticipated arguments.
 pub struct Descriptor {
+    /// Name of the argument (if any) used to look up its value and as placeholder in the help.
+    name: Option<String>,
+
     /// Anticipated value type for the argument.
     value_type: Type,
 
+    /// Number of values the argument takes.
+    arity: Arity,
+
     /// Description of the argument.
     description: String,
 }
 
 impl Descriptor {
-    /// Create a new argument descriptor.
+    /// Create a new argument descriptor taking exactly one value.
     pub fn new(value_type: Type, description: &str) -> Self {
         Descriptor {
+            name: None,
             value_type,
+            arity: Arity::Single,
             description: String::from(description),
         }
     }
 
+    /// Name the argument so its value can be looked up by name.
+    pub fn with_name(mut self, name: &str) -> Self {
+        self.name = Some(String::from(name));
+
+        self
+    }
+
+    /// Get the name of the argument (if any).
+    pub fn name(&self) -> Option<&String> {
+        self.name.as_ref()
+    }
+
+    /// Make the argument optional, falling back to the passed default value when not specified.
+    pub fn optional(mut self, default: Value) -> Self {
+        self.arity = Arity::Optional { default };
+
+        self
+    }
+
+    /// Let the argument take any number of values (including none) collected in a list.
+    pub fn zero_or_more(self) -> Self {
+        self.with_range(0, None)
+    }
+
+    /// Let the argument take at least one value, collecting all values in a list.
+    pub fn one_or_more(self) -> Self {
+        self.with_range(1, None)
+    }
+
+    /// Let the argument take between `min` and `max` values (no upper bound if `max` is `None`)
+    /// collected in a list.
+    pub fn with_range(mut self, min: usize, max: Option<usize>) -> Self {
+        assert!(max.is_none() || max.unwrap() >= min);
+
+        self.arity = Arity::Range { min, max };
+
+        self
+    }
+
     /// Get the type of the argument value.
     pub fn value_type(&self) -> &Type {
         &self.value_type
     }
 
+    /// Get the number of values the argument takes.
+    pub fn arity(&self) -> &Arity {
+        &self.arity
+    }
+
     /// Get the description of the argument.
     pub fn description(&self) -> &String {
         &self.description
@@ -1,7 +1,11 @@
 mod descriptor;
 mod arg_type;
+mod arity;
 mod value;
+mod values;
 
 pub use arg_type::Type;
+pub use arity::Arity;
 pub use value::Value;
+pub use values::Values;
 pub use descriptor::Descriptor;
@@ -1,12 +1,15 @@
 use std::fmt;
+use crate::error::parse_literal;
 use crate::arg;
 
 /// Possible argument values.
+#[derive(Debug, Clone, PartialEq)]
 pub enum Value {
     Bool { value: bool },
     Str { value: String },
     Int { value: i32 },
     Float { value: f64 },
+    List { value: Vec<Value> },
 }
 
 impl Value {
@@ -14,16 +17,16 @@ impl Value {
     pub fn parse(arg_type: &arg::Type, input: &str) -> crate::parser::Result<Value> {
         match arg_type {
             arg::Type::Bool => Ok(Value::Bool {
-                value: input.parse()?
+                value: parse_literal(input, arg_type)?
             }),
             arg::Type::Str => Ok(Value::Str {
                 value: String::from(input)
             }),
             arg::Type::Int => Ok(Value::Int {
-                value: input.parse()?
+                value: parse_literal(input, arg_type)?
             }),
             arg::Type::Float => Ok(Value::Float {
-                value: input.parse()?
+                value: parse_literal(input, arg_type)?
             }),
         }
     }
@@ -59,6 +62,14 @@ impl Value {
             _ => None,
         }
     }
+
+    /// Get the list of values of an argument taking a variable number of values.
+    pub fn list(&self) -> Option<&Vec<Value>> {
+        match self {
+            Value::List { value } => Some(value),
+            _ => None,
+        }
+    }
 }
 
 impl fmt::Display for Value {
@@ -68,6 +79,7 @@ impl fmt::Display for Value {
             Value::Str { value } => value.to_string(),
             Value::Int { value } => value.to_string(),
             Value::Float { value } => value.to_string(),
+            Value::List { value } => value.iter().map(|v| v.to_string()).collect::<Vec<String>>().join(", "),
         })
     }
 }
@@ -0,0 +1,68 @@
+use std::collections::HashMap;
+use std::ops::{Deref, Index};
+use crate::arg::Value;
+
+/// Parsed argument values handed to a group consumer.
+/// Dereferences to the argument values in the order they have been specified, while
+/// values of named arguments can additionally be looked up by their name.
+pub struct Values {
+    /// Parsed argument values.
+    values: Vec<Value>,
+
+    /// Positions of the argument values by argument name.
+    positions: HashMap<String, usize>,
+
+    /// Raw arguments following the end-of-options terminator `--`.
+    trailing: Vec<String>,
+}
+
+impl Values {
+    /// Create new argument values.
+    pub(crate) fn new(values: Vec<Value>, positions: HashMap<String, usize>, trailing: Vec<String>) -> Self {
+        Values {
+            values,
+            positions,
+            trailing,
+        }
+    }
+
+    /// Get the value of the argument with the passed name.
+    pub fn by_name(&self, name: &str) -> Option<&Value> {
+        self.positions.get(name).map(|pos| &self.values[*pos])
+    }
+
+    /// Get the raw arguments that followed the end-of-options terminator `--`.
+    /// They are passed verbatim, for example to be forwarded to a child process.
+    pub fn trailing(&self) -> &Vec<String> {
+        &self.trailing
+    }
+}
+
+impl Deref for Values {
+    type Target = [Value];
+
+    fn deref(&self) -> &Self::Target {
+        &self.values
+    }
+}
+
+impl Index<usize> for Values {
+    type Output = Value;
+
+    fn index(&self, index: usize) -> &Self::Output {
+        &self.values[index]
+    }
+}
+
+impl Index<&str> for Values {
+    type Output = Value;
+
+    /// Get the value of the argument with the passed name.
+    /// Panics if no argument with that name exists.
+    fn index(&self, name: &str) -> &Self::Output {
+        match self.by_name(name) {
+            Some(v) => v,
+            None => panic!("No argument named '{}'", name),
+        }
+    }
+}
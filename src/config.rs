@@ -0,0 +1,214 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use crate::error::ParserError;
+use crate::parser::Result;
+
+static COMMENT_PREFIXES: [char; 2] = ['#', ';'];
+static SECTION_START: char = '[';
+static SECTION_END: char = ']';
+static SECTION_PATH_SPLIT: char = '.';
+static KEY_VALUE_SPLIT: char = '=';
+static LIST_START: char = '[';
+static LIST_END: char = ']';
+static LIST_SPLIT: char = ',';
+
+/// Raw value of a configuration entry.
+pub(crate) enum RawValue {
+    Single(String),
+    List(Vec<String>),
+}
+
+/// Entry of a configuration file (for example `verbose = true`).
+pub(crate) struct Entry {
+    /// Path of the group the entry belongs to (for example `remote` and `add` for section `[remote.add]`).
+    pub section: Vec<String>,
+
+    /// Key of the entry referring to an option name.
+    pub key: String,
+
+    /// Raw value of the entry.
+    pub value: RawValue,
+
+    /// Line number (starting at 1) the entry has been declared in.
+    pub line: usize,
+}
+
+/// Configuration file of INI/TOML-subset syntax.
+/// Entries before any section belong to the root group, while sections like `[remote.add]`
+/// map to the group path of nested sub-commands.
+/// Values are either bare (`verbose = true`), quoted (`name = "John Doe"` or `name = 'John Doe'`)
+/// or lists of values (`include = ["a", "b"]`).
+pub(crate) struct ConfigFile {
+    /// Path the configuration file has been read from.
+    pub path: PathBuf,
+
+    /// Entries in the order they are declared in.
+    pub entries: Vec<Entry>,
+}
+
+impl ConfigFile {
+    /// Read the configuration file at the passed path.
+    /// Returns `None` if the file does not exist.
+    pub fn read(path: &Path) -> Result<Option<ConfigFile>> {
+        match fs::read_to_string(path) {
+            Ok(content) => Ok(Some(ConfigFile::parse(path, &content)?)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(ParserError::ConfigFile {
+                path: path.to_path_buf(),
+                line: None,
+                message: e.to_string(),
+            }),
+        }
+    }
+
+    /// Parse the passed configuration file content.
+    pub fn parse(path: &Path, content: &str) -> Result<ConfigFile> {
+        let mut section: Vec<String> = Vec::new();
+        let mut entries = Vec::new();
+
+        for (i, line) in content.lines().enumerate() {
+            let line_number = i + 1;
+            let line = line.trim();
+
+            if line.is_empty() || line.starts_with(&COMMENT_PREFIXES[..]) {
+                continue;
+            }
+
+            if line.starts_with(SECTION_START) {
+                let name = strip_comment(&line[1..]);
+                let name = match name.strip_suffix(SECTION_END) {
+                    Some(name) => name.trim(),
+                    None => return Err(syntax_error(path, line_number, "Expected section to be closed by ']'")),
+                };
+
+                section = if name.is_empty() {
+                    Vec::new()
+                } else {
+                    name.split(SECTION_PATH_SPLIT).map(|s| String::from(s.trim())).collect()
+                };
+                continue;
+            }
+
+            let (key, value) = match line.split_once(KEY_VALUE_SPLIT) {
+                Some((key, value)) => (key.trim(), value.trim()),
+                None => return Err(syntax_error(path, line_number, "Expected entry of the form 'key = value'")),
+            };
+            if key.is_empty() {
+                return Err(syntax_error(path, line_number, "Expected entry key before '='"));
+            }
+
+            let value = match parse_value(value) {
+                Some(value) => value,
+                None => return Err(syntax_error(path, line_number, &format!("Malformed value of entry '{}'", key))),
+            };
+
+            entries.push(Entry {
+                section: section.clone(),
+                key: String::from(key),
+                value,
+                line: line_number,
+            });
+        }
+
+        Ok(ConfigFile {
+            path: path.to_path_buf(),
+            entries,
+        })
+    }
+}
+
+/// Create an error for a syntax error in the configuration file.
+fn syntax_error(path: &Path, line: usize, message: &str) -> ParserError {
+    ParserError::ConfigFile {
+        path: path.to_path_buf(),
+        line: Some(line),
+        message: String::from(message),
+    }
+}
+
+/// Strip a trailing comment (separated by whitespace) from a line.
+fn strip_comment(line: &str) -> &str {
+    let mut end = line.len();
+    for prefix in COMMENT_PREFIXES.iter() {
+        for pattern in [format!(" {}", prefix), format!("\t{}", prefix)].iter() {
+            if let Some(pos) = line.find(pattern.as_str()) {
+                end = end.min(pos);
+            }
+        }
+    }
+
+    line[..end].trim_end()
+}
+
+/// Parse the raw value of an entry (either single value or list of values).
+fn parse_value(raw: &str) -> Option<RawValue> {
+    if raw.starts_with(LIST_START) {
+        let mut values = Vec::new();
+        let mut rest = raw[1..].trim_start();
+
+        loop {
+            if let Some(after) = rest.strip_prefix(LIST_END) {
+                return if strip_comment(after).is_empty() { Some(RawValue::List(values)) } else { None };
+            }
+
+            let (value, after) = parse_single_value(rest, &[LIST_SPLIT, LIST_END])?;
+            values.push(value);
+
+            rest = after.trim_start();
+            if let Some(after) = rest.strip_prefix(LIST_SPLIT) {
+                rest = after.trim_start();
+            } else if !rest.starts_with(LIST_END) {
+                return None;
+            }
+        }
+    }
+
+    let (value, rest) = parse_single_value(raw, &[])?;
+    if strip_comment(rest).is_empty() {
+        Some(RawValue::Single(value))
+    } else {
+        None
+    }
+}
+
+/// Parse a single (quoted or bare) value from the start of the passed string.
+/// Bare values end at one of the passed terminators, at a comment or at the end of the string.
+/// Returns the value and the rest of the string.
+fn parse_single_value<'a>(raw: &'a str, terminators: &[char]) -> Option<(String, &'a str)> {
+    let mut chars = raw.char_indices();
+
+    match chars.next() {
+        Some((_, '"')) => {
+            let mut value = String::new();
+            let mut escaped = false;
+            for (i, c) in chars {
+                if escaped {
+                    value.push(match c {
+                        'n' => '\n',
+                        't' => '\t',
+                        c => c,
+                    });
+                    escaped = false;
+                } else if c == '\\' {
+                    escaped = true;
+                } else if c == '"' {
+                    return Some((value, &raw[i + 1..]));
+                } else {
+                    value.push(c);
+                }
+            }
+
+            None // Missing closing quote
+        }
+        Some((_, '\'')) => {
+            let end = raw[1..].find('\'')? + 1;
+            Some((String::from(&raw[1..end]), &raw[end + 1..]))
+        }
+        _ => {
+            let end = raw.find(terminators).unwrap_or(raw.len());
+            let value = strip_comment(&raw[..end]);
+            Some((String::from(value.trim()), &raw[end..]))
+        }
+    }
+}
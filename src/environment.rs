@@ -0,0 +1,24 @@
+use std::collections::HashMap;
+use std::env;
+
+/// Source of environment variables consulted for option values not specified on the command line.
+pub trait Environment {
+    /// Get the value of the environment variable with the passed name (if set).
+    fn var(&self, name: &str) -> Option<String>;
+}
+
+/// Environment of the current process.
+pub struct ProcessEnvironment {}
+
+impl Environment for ProcessEnvironment {
+    fn var(&self, name: &str) -> Option<String> {
+        env::var(name).ok()
+    }
+}
+
+/// In-memory environment mapping variable names to values.
+impl Environment for HashMap<String, String> {
+    fn var(&self, name: &str) -> Option<String> {
+        self.get(name).cloned()
+    }
+}
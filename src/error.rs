@@ -1,39 +1,406 @@
 use std::error::Error;
 use std::fmt;
+use std::path::PathBuf;
+use std::str::FromStr;
+use crate::{arg, option};
 
-#[derive(Debug)]
-pub struct ParserError {
-    pub message: String,
+/// Error occurring while parsing the command line.
+/// Indices refer to the position of the offending raw command line argument (after expanding
+/// response files), where the program name is at index 0.
+/// Group paths hold the names of the groups on the path from the root group to the group
+/// providing the command context the error occurred in.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParserError {
+    /// Option not known in the command context, with the most similar known options.
+    UnknownOption {
+        token: String,
+        index: Option<usize>,
+        suggestions: Vec<String>,
+        group_path: Vec<String>,
+    },
+
+    /// Abbreviated option name being a prefix of multiple options.
+    AmbiguousOption {
+        token: String,
+        index: Option<usize>,
+        candidates: Vec<String>,
+        group_path: Vec<String>,
+    },
+
+    /// Sub-command not known by the context group, with the most similar known sub-commands.
+    UnknownSubCommand {
+        token: String,
+        index: Option<usize>,
+        suggestions: Vec<String>,
+        group_path: Vec<String>,
+    },
+
+    /// Abbreviated sub-command name being a prefix of multiple sub-commands.
+    AmbiguousSubCommand {
+        token: String,
+        index: Option<usize>,
+        candidates: Vec<String>,
+        group_path: Vec<String>,
+    },
+
+    /// Option taking a value specified without one.
+    MissingValue {
+        option: String,
+        index: Option<usize>,
+        expected: option::Type,
+        group_path: Vec<String>,
+    },
+
+    /// Value specified for an option not taking one (for example a negated or counted flag).
+    UnexpectedValue {
+        option: String,
+        value: String,
+        index: Option<usize>,
+        group_path: Vec<String>,
+    },
+
+    /// Option allowed only once specified multiple times.
+    DuplicateOption {
+        option: String,
+        index: Option<usize>,
+        group_path: Vec<String>,
+    },
+
+    /// Option value not parsable as the expected type.
+    /// The source tells where the value originates from (command line, environment or configuration file).
+    /// Source and expected type are boxed to keep the error small.
+    InvalidValue {
+        option: String,
+        value: String,
+        source: Box<option::Source>,
+        index: Option<usize>,
+        expected: Box<option::Type>,
+        group_path: Vec<String>,
+    },
+
+    /// Argument value not parsable as the expected type.
+    /// The position is the one-based position among the arguments of the command context.
+    InvalidArgument {
+        value: String,
+        position: usize,
+        index: Option<usize>,
+        expected: arg::Type,
+        group_path: Vec<String>,
+    },
+
+    /// Required options not specified (by name).
+    MissingRequiredOptions {
+        options: Vec<String>,
+        group_path: Vec<String>,
+    },
+
+    /// Number of arguments not matching the arguments of the command context.
+    WrongArgumentCount {
+        expected: arg::Arity,
+        actual: usize,
+        group_path: Vec<String>,
+    },
+
+    /// Conflicting option declarations in the group specifications.
+    DefinitionConflict {
+        name: String,
+        message: String,
+        group_path: Vec<String>,
+    },
+
+    /// Configuration file not readable or malformed (in the passed line).
+    ConfigFile {
+        path: PathBuf,
+        line: Option<usize>,
+        message: String,
+    },
+
+    /// Response file not readable, malformed or referencing itself.
+    ResponseFile {
+        path: PathBuf,
+        message: String,
+    },
+
+    /// Literal not parsable as value of the expected type.
+    InvalidLiteral {
+        value: String,
+        expected: String,
+    },
+
+    /// Multiple errors collected in one pass (see `ParseOptions::collect_errors`), ordered by index.
+    Multiple {
+        errors: Vec<ParserError>,
+    },
+}
+
+impl ParserError {
+    /// Get the index of the offending raw command line argument (if any).
+    pub fn index(&self) -> Option<usize> {
+        match self {
+            ParserError::UnknownOption { index, .. }
+            | ParserError::AmbiguousOption { index, .. }
+            | ParserError::UnknownSubCommand { index, .. }
+            | ParserError::AmbiguousSubCommand { index, .. }
+            | ParserError::MissingValue { index, .. }
+            | ParserError::UnexpectedValue { index, .. }
+            | ParserError::DuplicateOption { index, .. }
+            | ParserError::InvalidValue { index, .. }
+            | ParserError::InvalidArgument { index, .. } => *index,
+            _ => None,
+        }
+    }
+
+    /// Get the path of the group providing the command context the error occurred in (if known).
+    pub fn group_path(&self) -> Option<&[String]> {
+        match self {
+            ParserError::UnknownOption { group_path, .. }
+            | ParserError::AmbiguousOption { group_path, .. }
+            | ParserError::UnknownSubCommand { group_path, .. }
+            | ParserError::AmbiguousSubCommand { group_path, .. }
+            | ParserError::MissingValue { group_path, .. }
+            | ParserError::UnexpectedValue { group_path, .. }
+            | ParserError::DuplicateOption { group_path, .. }
+            | ParserError::InvalidValue { group_path, .. }
+            | ParserError::InvalidArgument { group_path, .. }
+            | ParserError::MissingRequiredOptions { group_path, .. }
+            | ParserError::WrongArgumentCount { group_path, .. }
+            | ParserError::DefinitionConflict { group_path, .. } => Some(group_path),
+            _ => None,
+        }
+    }
+
+    /// Set the index of the offending raw command line argument, unless already known.
+    pub(crate) fn at_index(mut self, position: usize) -> Self {
+        match &mut self {
+            ParserError::UnknownOption { index, .. }
+            | ParserError::AmbiguousOption { index, .. }
+            | ParserError::UnknownSubCommand { index, .. }
+            | ParserError::AmbiguousSubCommand { index, .. }
+            | ParserError::MissingValue { index, .. }
+            | ParserError::UnexpectedValue { index, .. }
+            | ParserError::DuplicateOption { index, .. }
+            | ParserError::InvalidValue { index, .. }
+            | ParserError::InvalidArgument { index, .. } if index.is_none() => *index = Some(position),
+            _ => {}
+        }
+
+        self
+    }
+
+    /// Set the path of the group providing the command context the error occurred in.
+    pub(crate) fn in_group(mut self, path: &[String]) -> Self {
+        match &mut self {
+            ParserError::UnknownOption { group_path, .. }
+            | ParserError::AmbiguousOption { group_path, .. }
+            | ParserError::UnknownSubCommand { group_path, .. }
+            | ParserError::AmbiguousSubCommand { group_path, .. }
+            | ParserError::MissingValue { group_path, .. }
+            | ParserError::UnexpectedValue { group_path, .. }
+            | ParserError::DuplicateOption { group_path, .. }
+            | ParserError::InvalidValue { group_path, .. }
+            | ParserError::InvalidArgument { group_path, .. }
+            | ParserError::MissingRequiredOptions { group_path, .. }
+            | ParserError::WrongArgumentCount { group_path, .. }
+            | ParserError::DefinitionConflict { group_path, .. } => *group_path = path.to_vec(),
+            _ => {}
+        }
+
+        self
+    }
 }
 
 impl Error for ParserError {}
 
 impl fmt::Display for ParserError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", self.message)
+        match self {
+            ParserError::UnknownOption { token, suggestions, .. } =>
+                write!(f, "Option '{}' is unknown in the command context{}", token, did_you_mean(suggestions)),
+            ParserError::AmbiguousOption { token, candidates, .. } =>
+                write!(f, "Option '{}' is ambiguous, candidates are: {}", token, quoted(candidates)),
+            ParserError::UnknownSubCommand { token, suggestions, .. } =>
+                write!(f, "Sub-command '{}' is unknown{}", token, did_you_mean(suggestions)),
+            ParserError::AmbiguousSubCommand { token, candidates, .. } =>
+                write!(f, "Sub-command '{}' is ambiguous, candidates are: {}", token, quoted(candidates)),
+            ParserError::MissingValue { option, .. } =>
+                write!(f, "Encountered option '{}' without value that is not of type boolean. Specify a value for the option.", option),
+            ParserError::UnexpectedValue { option, value, .. } =>
+                write!(f, "Option '{}' does not take a value (got '{}')", option, value),
+            ParserError::DuplicateOption { option, .. } =>
+                write!(f, "Option '{}' specified more than once", option),
+            ParserError::InvalidValue { option, value, source, expected, .. } => match source.as_ref() {
+                option::Source::CommandLine { spelling } =>
+                    write!(f, "Expected value '{}' of option '{}' to be of type '{}'", value, spelling, expected),
+                option::Source::Environment { variable } =>
+                    write!(f, "Expected value '{}' of environment variable '{}' (option '--{}') to be of type '{}'", value, variable, option, expected),
+                option::Source::ConfigFile { path, line } =>
+                    write!(f, "{}:{}: Expected value '{}' of option '--{}' to be of type '{}'", path.display(), line, value, option, expected),
+                option::Source::Default =>
+                    write!(f, "Expected default value '{}' of option '--{}' to be of type '{}'", value, option, expected),
+            },
+            ParserError::InvalidArgument { value, position, expected, .. } =>
+                write!(f, "Expected argument '{}' at position {} to be of type '{}'", value, position, expected),
+            ParserError::MissingRequiredOptions { options, .. } => {
+                let options: Vec<String> = options.iter().map(|o| format!("--{}", o)).collect();
+                write!(f, "Missing required options: {}", quoted(&options))
+            }
+            ParserError::WrongArgumentCount { expected, actual, .. } =>
+                write!(f, "Expected to have {} arguments but got {}", expected, actual),
+            ParserError::DefinitionConflict { message, .. } => write!(f, "{}", message),
+            ParserError::ConfigFile { path, line: Some(line), message } => write!(f, "{}:{}: {}", path.display(), line, message),
+            ParserError::ConfigFile { path, line: None, message } =>
+                write!(f, "Could not read configuration file '{}': {}", path.display(), message),
+            ParserError::ResponseFile { path, message } => write!(f, "Response file '{}' {}", path.display(), message),
+            ParserError::InvalidLiteral { value, expected } => write!(f, "Could not parse '{}' as {}", value, expected),
+            ParserError::Multiple { errors } => {
+                let messages: Vec<String> = errors.iter().map(|e| e.to_string()).collect();
+                write!(f, "{}", messages.join("\n"))
+            }
+        }
+    }
+}
+
+/// Format the passed names quoted and separated by commas (for example `'a', 'b'`).
+fn quoted(names: &[String]) -> String {
+    names.iter().map(|n| format!("'{}'", n)).collect::<Vec<String>>().join(", ")
+}
+
+/// Format a hint listing the passed suggestions (for example `, did you mean '--verbose'?`).
+/// Returns an empty string when there are no suggestions.
+fn did_you_mean(suggestions: &[String]) -> String {
+    match suggestions.len() {
+        0 => String::new(),
+        1 => format!(", did you mean '{}'?", suggestions[0]),
+        _ => format!(", did you mean one of {}?", quoted(suggestions)),
+    }
+}
+
+/// Parse the passed literal as value of the passed expected type.
+pub(crate) fn parse_literal<T: FromStr>(value: &str, expected: &dyn fmt::Display) -> Result<T, ParserError> {
+    value.parse().map_err(|_| ParserError::InvalidLiteral {
+        value: String::from(value),
+        expected: expected.to_string(),
+    })
+}
+
+/// Exit code for usage errors (for example unknown options or invalid values).
+static USAGE_EXIT_CODE: i32 = 2;
+
+/// Exit code for failed commands without explicit exit code.
+static FAILURE_EXIT_CODE: i32 = 1;
+
+/// Result of a command returned by fallible consumers (see `Group::new_fallible`).
+pub type CommandResult = Result<(), CommandError>;
+
+/// Failure of a command reported by its consumer.
+/// Any error is convertible to a command error, thus consumers may use the `?` operator.
+pub struct CommandError {
+    /// Error causing the failure (if any).
+    error: Option<Box<dyn Error>>,
+
+    /// Exit code to terminate the process with.
+    exit_code: i32,
+}
+
+impl CommandError {
+    /// Create a command error caused by the passed error (exiting with code 1).
+    pub fn new<E: Into<Box<dyn Error>>>(error: E) -> Self {
+        CommandError {
+            error: Some(error.into()),
+            exit_code: FAILURE_EXIT_CODE,
+        }
+    }
+
+    /// Create a command error without cause only terminating the process with the passed exit code.
+    pub fn exit(exit_code: i32) -> Self {
+        CommandError {
+            error: None,
+            exit_code,
+        }
+    }
+
+    /// Set the exit code to terminate the process with.
+    pub fn with_exit_code(mut self, exit_code: i32) -> Self {
+        self.exit_code = exit_code;
+        self
+    }
+
+    /// Get the error causing the failure (if any).
+    pub fn error(&self) -> Option<&dyn Error> {
+        self.error.as_deref()
+    }
+
+    /// Get the exit code to terminate the process with.
+    pub fn exit_code(&self) -> i32 {
+        self.exit_code
+    }
+}
+
+impl<E: Into<Box<dyn Error>>> From<E> for CommandError {
+    fn from(error: E) -> Self {
+        CommandError::new(error)
+    }
+}
+
+impl fmt::Debug for CommandError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CommandError")
+            .field("error", &self.error)
+            .field("exit_code", &self.exit_code)
+            .finish()
     }
 }
 
-impl From<std::str::ParseBoolError> for ParserError {
-    fn from(e: std::str::ParseBoolError) -> Self {
-        ParserError {
-            message: format!("Could not parse boolean: {}", e.to_string())
+impl fmt::Display for CommandError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.error {
+            Some(error) => write!(f, "{}", error),
+            None => write!(f, "Command exited with code {}", self.exit_code),
         }
     }
 }
 
-impl From<std::num::ParseIntError> for ParserError {
-    fn from(e: std::num::ParseIntError) -> Self {
-        ParserError {
-            message: format!("Could not parse integer: {}", e.to_string())
+/// Error returned when parsing the command line or running the command failed.
+#[derive(Debug)]
+pub enum RunError {
+    /// Command line could not be parsed (usage error).
+    Parser(ParserError),
+
+    /// Consumer of the command reported a failure.
+    Command(CommandError),
+}
+
+impl RunError {
+    /// Get the conventional exit code to terminate the process with: 2 for usage errors, while
+    /// failed commands define their exit code themselves.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            RunError::Parser(_) => USAGE_EXIT_CODE,
+            RunError::Command(e) => e.exit_code(),
         }
     }
 }
 
-impl From<std::num::ParseFloatError> for ParserError {
-    fn from(e: std::num::ParseFloatError) -> Self {
-        ParserError {
-            message: format!("Could not parse float: {}", e.to_string())
+impl Error for RunError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            RunError::Parser(e) => Some(e),
+            RunError::Command(_) => None,
         }
     }
 }
+
+impl fmt::Display for RunError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RunError::Parser(e) => write!(f, "{}", e),
+            RunError::Command(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl From<ParserError> for RunError {
+    fn from(e: ParserError) -> Self {
+        RunError::Parser(e)
+    }
+}
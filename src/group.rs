@@ -1,9 +1,32 @@
-use std::rc::Rc;
-use crate::{option, arg};
-use std::collections::HashMap;
+use std::sync::Arc;
+use std::marker::PhantomData;
+use crate::{option, arg, CommandResult};
+use std::collections::{HashMap, HashSet};
 
 /// Consumer for the parsed result (arguments and options).
-type ParserResultConsumer = Box<dyn Fn(&Vec<arg::Value>, &HashMap<&str, option::Value>)>;
+type ParserResultConsumer = Box<dyn Fn(&arg::Values, &option::Values)>;
+
+/// Thread-safe consumer for the parsed result (arguments and options).
+type SyncParserResultConsumer = Box<dyn Fn(&arg::Values, &option::Values) + Send + Sync>;
+
+/// Consumer for the parsed result (arguments and options) reporting whether the command succeeded.
+type CommandConsumer = Box<dyn Fn(&arg::Values, &option::Values) -> CommandResult>;
+
+/// Thread-safe consumer for the parsed result (arguments and options) reporting whether the
+/// command succeeded.
+type SyncCommandConsumer = Box<dyn Fn(&arg::Values, &option::Values) -> CommandResult + Send + Sync>;
+
+/// Consumer for the parsed result (arguments and options) additionally taking the context passed
+/// to the parser (for example application state or a database handle).
+pub type Consumer<C = ()> = dyn Fn(&mut C, &arg::Values, &option::Values) -> CommandResult;
+
+/// Thread-safe consumer taking the context passed to the parser (see `SyncGroup`).
+pub type SyncConsumer<C = ()> = dyn Fn(&mut C, &arg::Values, &option::Values) -> CommandResult + Send + Sync;
+
+/// Group whose consumers are thread-safe, thus the group (and a parser for it) is `Send + Sync`
+/// and may for example be stored in a `static` and used to parse from multiple threads.
+/// Create it via `Group::new_sync`, `Group::new_fallible_sync` or `Group::with_context_sync`.
+pub type SyncGroup<C = ()> = Group<C, SyncConsumer<C>>;
 
 /// A group is a collection of possible CLI options and arguments.
 /// Essentially it provides the context of a action called via CLI.
@@ -14,45 +37,117 @@ type ParserResultConsumer = Box<dyn Fn(&Vec<arg::Value>, &HashMap<&str, option::
 /// When options are defined on the root group, they are available to every
 /// child group as well, while options defined on a child group are only available to the
 /// child group and its children.
-pub struct Group {
+/// Options marked as local (`option::Descriptor::local`) are not inherited by child groups,
+/// while child groups may override or hide inherited options.
+/// Consumers may take a context of type `C` handed to the parser (see `Group::with_context`),
+/// while groups created via `Group::new` take no context at all.
+/// The type of the consumers is `F`, which is either `Consumer<C>` or the thread-safe
+/// `SyncConsumer<C>` (see `SyncGroup`), where all groups of a tree share the same consumer type.
+pub struct Group<C = (), F: ?Sized = Consumer<C>> {
     /// Descriptors for all anticipated options.
-    options: Option<HashMap<Rc<String>, Rc<option::Descriptor>>>,
+    options: Option<HashMap<Arc<String>, Arc<option::Descriptor>>>,
+
+    /// Names of options overriding inherited options.
+    overridden_options: HashSet<String>,
+
+    /// Names of inherited options hidden in this group and its children.
+    hidden_options: HashSet<String>,
 
     /// Descriptors for all anticipated arguments.
     arguments: Vec<arg::Descriptor>,
 
     /// Child groups.
-    children: HashMap<Rc<String>, Rc<Group>>,
+    children: HashMap<Arc<String>, Arc<Group<C, F>>>,
 
     /// Lookup of child groups by known aliases (including name).
-    children_lookup: HashMap<Rc<String>, Rc<Group>>,
+    children_lookup: HashMap<Arc<String>, Arc<Group<C, F>>>,
 
     /// Lookup of aliases by group name.
-    alias_lookup: HashMap<Rc<String>, Vec<Rc<String>>>,
+    alias_lookup: HashMap<Arc<String>, Vec<Arc<String>>>,
+
+    /// Lookup of child group names by known aliases (including name).
+    child_names: HashMap<Arc<String>, Arc<String>>,
 
-    /// Consumer called with the parsed options and arguments for this group.
-    consumer: ParserResultConsumer,
+    /// Consumer called with the context, parsed options and arguments for this group.
+    consumer: Box<F>,
 
     /// Description of the group.
     description: String,
+
+    /// Type of the context taken by the consumers.
+    context_type: PhantomData<fn(&mut C)>,
 }
 
 impl Group {
     /// Create new group configuration.
     pub fn new(consumer: ParserResultConsumer, description: &str) -> Self {
+        Group::new_fallible(Box::new(move |args, options| {
+            consumer(args, options);
+            Ok(())
+        }), description)
+    }
+
+    /// Create new group configuration with a consumer reporting failures of the command, which are
+    /// propagated to the caller of the parser.
+    pub fn new_fallible(consumer: CommandConsumer, description: &str) -> Self {
+        Group::with_context(Box::new(move |_, args, options| consumer(args, options)), description)
+    }
+}
+
+impl SyncGroup {
+    /// Create new group configuration with a thread-safe consumer.
+    pub fn new_sync(consumer: SyncParserResultConsumer, description: &str) -> Self {
+        Group::new_fallible_sync(Box::new(move |args, options| {
+            consumer(args, options);
+            Ok(())
+        }), description)
+    }
+
+    /// Create new group configuration with a thread-safe consumer reporting failures of the command.
+    pub fn new_fallible_sync(consumer: SyncCommandConsumer, description: &str) -> Self {
+        Group::with_context_sync(Box::new(move |_, args, options| consumer(args, options)), description)
+    }
+}
+
+impl<C> Group<C> {
+    /// Create new group configuration with a consumer taking the context passed to the parser
+    /// (see `parser::parse_from_with`).
+    pub fn with_context(consumer: Box<Consumer<C>>, description: &str) -> Self {
+        Group::with_consumer(consumer, description)
+    }
+}
+
+impl<C> SyncGroup<C> {
+    /// Create new group configuration with a thread-safe consumer taking the context passed to the parser.
+    pub fn with_context_sync(consumer: Box<SyncConsumer<C>>, description: &str) -> Self {
+        Group::with_consumer(consumer, description)
+    }
+}
+
+impl<C, F: ?Sized> Group<C, F> {
+    /// Create new group configuration with the passed consumer.
+    fn with_consumer(consumer: Box<F>, description: &str) -> Self {
         Group {
             options: Some(HashMap::new()),
+            overridden_options: HashSet::new(),
+            hidden_options: HashSet::new(),
             arguments: Vec::new(),
             children: HashMap::new(),
             children_lookup: HashMap::new(),
             alias_lookup: HashMap::new(),
+            child_names: HashMap::new(),
             consumer,
             description: String::from(description),
+            context_type: PhantomData,
         }
     }
 
     /// Add an argument to this group.
     pub fn add_argument(mut self, argument: arg::Descriptor) -> Self {
+        if let Some(name) = argument.name() {
+            assert!(!self.arguments.iter().any(|a| a.name() == Some(name)));
+        }
+
         self.arguments.push(argument);
 
         self
@@ -67,65 +162,114 @@ impl Group {
     pub fn add_option(mut self, option: option::Descriptor) -> Self {
         assert!(!&self.options.as_ref().unwrap().contains_key(option.name()));
 
-        self.options.as_mut().unwrap().insert(option.take_name(), Rc::new(option));
+        self.options.as_mut().unwrap().insert(option.take_name(), Arc::new(option));
 
         self
     }
 
+    /// Add an option overriding the inherited option of the same name (for example to change its
+    /// default value, description or type) in this group and its children.
+    pub fn override_option(mut self, option: option::Descriptor) -> Self {
+        self.overridden_options.insert(option.name().clone());
+
+        self.add_option(option)
+    }
+
+    /// Check whether the option with the passed name overrides an inherited option.
+    pub fn overrides_option(&self, name: &str) -> bool {
+        self.overridden_options.contains(name)
+    }
+
+    /// Hide the inherited option with the passed name in this group and its children.
+    /// Parsing fails with a `ParserError::DefinitionConflict` if no such option is inherited.
+    pub fn hide_option(mut self, name: &str) -> Self {
+        self.hidden_options.insert(String::from(name));
+
+        self
+    }
+
+    /// Get the names of inherited options hidden in this group and its children.
+    pub fn get_hidden_options(&self) -> &HashSet<String> {
+        &self.hidden_options
+    }
+
     /// Take ownership of all specified options.
-    pub fn get_options(&self) -> &HashMap<Rc<String>, Rc<option::Descriptor>> {
+    pub fn get_options(&self) -> &HashMap<Arc<String>, Arc<option::Descriptor>> {
         self.options.as_ref().unwrap()
     }
 
     /// Add a child group known by the passed name.
-    pub fn add_child(mut self, name: &str, aliases: Option<Vec<&str>>, group: Group) -> Self {
-        let name = Rc::new(String::from(name));
-        let group = Rc::new(group);
+    pub fn add_child(mut self, name: &str, aliases: Option<Vec<&str>>, group: Group<C, F>) -> Self {
+        let name = Arc::new(String::from(name));
+        let group = Arc::new(group);
 
         assert!(!self.children.contains_key(&name));
-        self.children.insert(Rc::clone(&name), Rc::clone(&group));
+        self.children.insert(Arc::clone(&name), Arc::clone(&group));
 
         // Insert aliases in lookup
         assert!(!self.children_lookup.contains_key(&name));
-        self.children_lookup.insert(Rc::clone(&name), Rc::clone(&group));
+        self.children_lookup.insert(Arc::clone(&name), Arc::clone(&group));
+        self.child_names.insert(Arc::clone(&name), Arc::clone(&name));
 
-        if aliases.is_some() {
-            let aliases = aliases.unwrap();
+        if let Some(aliases) = aliases {
             let mut alias_vec = Vec::with_capacity(aliases.len());
             for alias in aliases {
-                let alias = Rc::new(String::from(alias));
-                alias_vec.push(Rc::clone(&alias));
+                let alias = Arc::new(String::from(alias));
+                alias_vec.push(Arc::clone(&alias));
 
                 assert!(!self.children_lookup.contains_key(&alias));
-                self.children_lookup.insert(Rc::clone(&alias), Rc::clone(&group));
+                self.children_lookup.insert(Arc::clone(&alias), Arc::clone(&group));
+                self.child_names.insert(Arc::clone(&alias), Arc::clone(&name));
             }
 
-            self.alias_lookup.insert(Rc::clone(&name), alias_vec);
+            self.alias_lookup.insert(Arc::clone(&name), alias_vec);
         }
 
         self
     }
 
     /// Get known aliases for the passed group name.
-    pub fn get_aliases_for_group_name(&self, group_name: &String) -> Option<&Vec<Rc<String>>> {
+    pub fn get_aliases_for_group_name(&self, group_name: &String) -> Option<&Vec<Arc<String>>> {
         self.alias_lookup.get(group_name)
     }
 
     /// Get children of the group.
-    pub fn get_children(&self) -> &HashMap<Rc<String>, Rc<Group>> {
+    pub fn get_children(&self) -> &HashMap<Arc<String>, Arc<Group<C, F>>> {
         &self.children
     }
 
     /// Get a child known for the passed alias (including name).
-    pub fn get_child_known_for(&self, alias: &str) -> Option<Rc<Group>> {
+    pub fn get_child_known_for(&self, alias: &str) -> Option<Arc<Group<C, F>>> {
         match self.children_lookup.get(&String::from(alias)) {
-            Some(v) => Some(Rc::clone(v)),
+            Some(v) => Some(Arc::clone(v)),
             None => None
         }
     }
 
+    /// Get the name of the child known for the passed alias (including name).
+    pub fn get_child_name_known_for(&self, alias: &str) -> Option<&Arc<String>> {
+        self.child_names.get(&String::from(alias))
+    }
+
+    /// Get all names and aliases the children of the group are known for.
+    pub fn get_child_names_known(&self) -> Vec<&Arc<String>> {
+        self.children_lookup.keys().collect()
+    }
+
+    /// Get the names of all children known for an alias (including name) starting with the passed prefix.
+    pub fn get_child_names_known_for_prefix(&self, prefix: &str) -> Vec<&Arc<String>> {
+        let mut names: Vec<&Arc<String>> = self.children_lookup.keys()
+            .filter(|alias| alias.starts_with(prefix))
+            .filter_map(|alias| self.get_child_name_known_for(alias))
+            .collect();
+        names.sort();
+        names.dedup();
+
+        names
+    }
+
     /// Get the registered function to consume the parsed arguments and options.
-    pub fn get_consumer(&self) -> &ParserResultConsumer {
+    pub fn get_consumer(&self) -> &F {
         &self.consumer
     }
 
@@ -1,5 +1,7 @@
 use crate::{Group, option, arg};
-use std::rc::Rc;
+use crate::group::Consumer;
+use std::sync::Arc;
+use std::io;
 
 /// Entry in the help documentation.
 pub struct HelpEntry<K, V> {
@@ -7,40 +9,61 @@ pub struct HelpEntry<K, V> {
     pub value: V,
 }
 
-/// Help formatter to use when printing the help documentation.
-pub trait HelpPrinter {
-    /// Print the help documentation.
+/// Help entry of a sub-command (its name and group).
+pub type SubcommandEntry<'a, C = (), F = Consumer<C>> = HelpEntry<&'a Arc<String>, &'a Arc<Group<C, F>>>;
+
+/// Help entry of an option (its name and descriptor).
+pub type OptionEntry<'a> = HelpEntry<&'a Arc<String>, &'a Arc<option::Descriptor>>;
+
+/// Help formatter to use when printing the help documentation (of groups with consumers of type `F`
+/// taking a context of type `C`).
+pub trait HelpPrinter<C = (), F: ?Sized = Consumer<C>> {
+    /// Write the help documentation to the passed writer (for example the output of a REPL).
+    fn write(
+        &self,
+        out: &mut dyn io::Write,
+        group: &Group<C, F>,
+        subcommand_entries: &[SubcommandEntry<C, F>],
+        option_entries: &[OptionEntry],
+        arg_entries: &[arg::Descriptor],
+    ) -> io::Result<()>;
+
+    /// Print the help documentation to stdout.
     fn print(
         &self,
-        group: &Group,
-        subcommand_entries: &Vec<HelpEntry<&Rc<String>, &Rc<Group>>>,
-        option_entries: &Vec<HelpEntry<&Rc<String>, &Rc<option::Descriptor>>>,
-        arg_entries: &Vec<arg::Descriptor>,
-    );
+        group: &Group<C, F>,
+        subcommand_entries: &[SubcommandEntry<C, F>],
+        option_entries: &[OptionEntry],
+        arg_entries: &[arg::Descriptor],
+    ) {
+        self.write(&mut io::stdout(), group, subcommand_entries, option_entries, arg_entries)
+            .expect("failed printing to stdout");
+    }
 }
 
 /// Default help printer used when none is specified.
 pub struct DefaultHelpPrinter {}
 
-impl HelpPrinter for DefaultHelpPrinter {
-    fn print(
+impl<C, F: ?Sized> HelpPrinter<C, F> for DefaultHelpPrinter {
+    fn write(
         &self,
-        group: &Group,
-        subcommand_entries: &Vec<HelpEntry<&Rc<String>, &Rc<Group>>>,
-        option_entries: &Vec<HelpEntry<&Rc<String>, &Rc<option::Descriptor>>>,
-        arg_entries: &Vec<arg::Descriptor>,
-    ) {
-        println!("\n### DESCRIPTION ###");
-        println!("{description}", description = group.description());
+        out: &mut dyn io::Write,
+        group: &Group<C, F>,
+        subcommand_entries: &[SubcommandEntry<C, F>],
+        option_entries: &[OptionEntry],
+        arg_entries: &[arg::Descriptor],
+    ) -> io::Result<()> {
+        writeln!(out, "\n### DESCRIPTION ###")?;
+        writeln!(out, "{description}", description = group.description())?;
 
-        println!("\n### SUB-COMMANDS ###");
-        if subcommand_entries.len() == 0 {
-            println!("(No sub-commands available...)");
+        writeln!(out, "\n### SUB-COMMANDS ###")?;
+        if subcommand_entries.is_empty() {
+            writeln!(out, "(No sub-commands available...)")?;
         } else {
             // Get longest sub-command name
             let mut max_length = 0;
             for entry in subcommand_entries {
-                let prefix = match group.get_aliases_for_group_name(&entry.key) {
+                let prefix = match group.get_aliases_for_group_name(entry.key) {
                     Some(aliases) => format!(
                         "{name} ({aliases})",
                         name = entry.key,
@@ -54,7 +77,7 @@ impl HelpPrinter for DefaultHelpPrinter {
             }
 
             for entry in subcommand_entries {
-                let prefix = match group.get_aliases_for_group_name(&entry.key) {
+                let prefix = match group.get_aliases_for_group_name(entry.key) {
                     Some(aliases) => format!(
                         "{name} ({aliases})",
                         name = entry.key,
@@ -62,71 +85,118 @@ impl HelpPrinter for DefaultHelpPrinter {
                     ),
                     None => entry.key.to_string(),
                 };
-                println!("  - {prefix:<width$} | {description}", prefix = prefix, width = max_length, description = entry.value.description());
+                writeln!(out, "  - {prefix:<width$} | {description}", prefix = prefix, width = max_length, description = entry.value.description())?;
             }
         }
 
-        println!("\n### OPTIONS ###");
-        if option_entries.len() == 0 {
-            println!("(No options available...)");
+        writeln!(out, "\n### OPTIONS ###")?;
+        if option_entries.is_empty() {
+            writeln!(out, "(No options available...)")?;
         } else {
             // Get longest option name
             let mut max_length = 0;
             for entry in option_entries {
-                let aliases = entry.value.get_aliases();
-                let prefix = if aliases.len() == 0 {
-                    format!("{name} <{type_name}>", name = entry.key, type_name = entry.value.value_type())
-                } else {
-                    format!(
-                        "{name} ({aliases}) <{type_name}>",
-                        name = entry.key,
-                        aliases = aliases.iter().map(|s| format!("-{}", s)).collect::<Vec<String>>().join(", "),
-                        type_name = entry.value.value_type()
-                    )
-                };
+                let prefix = option_prefix(entry.key, entry.value);
                 if prefix.len() > max_length {
                     max_length = prefix.len();
                 }
             }
 
             for entry in option_entries {
-                let aliases = entry.value.get_aliases();
-                let prefix = if aliases.len() == 0 {
-                    format!("{name} <{type_name}>", name = entry.key, type_name = entry.value.value_type())
-                } else {
-                    format!(
-                        "{name} ({aliases}) <{type_name}>",
-                        name = entry.key,
-                        aliases = aliases.iter().map(|s| format!("-{}", s)).collect::<Vec<String>>().join(", "),
-                        type_name = entry.value.value_type()
-                    )
-                };
-                println!("  --{prefix:<width$} | {description}", prefix = prefix, width = max_length, description = entry.value.description());
+                let prefix = option_prefix(entry.key, entry.value);
+                writeln!(out, "  {prefix:<width$} | {description}", prefix = prefix, width = max_length, description = option_description(entry.value))?;
             }
         }
 
-        println!("\n### ARGUMENTS ###");
-        if arg_entries.len() == 0 {
-            println!("(Command expects no arguments...)");
+        writeln!(out, "\n### ARGUMENTS ###")?;
+        if arg_entries.is_empty() {
+            writeln!(out, "(Command expects no arguments...)")?;
         } else {
             let mut max_length = 0;
-            for i in 0..arg_entries.len() {
-                let arg_d = &arg_entries[i];
-
-                let prefix = format!("{num}. <{type_name}>", num = i + 1, type_name = arg_d.value_type());
+            for (i, arg_d) in arg_entries.iter().enumerate() {
+                let prefix = argument_prefix(i, arg_d);
                 if prefix.len() > max_length {
                     max_length = prefix.len();
                 }
             }
 
-            for i in 0..arg_entries.len() {
-                let arg_d = &arg_entries[i];
-
-                let prefix = format!("{num}. <{type_name}>", num = i + 1, type_name = arg_d.value_type());
-                println!("  {prefix:<width$} | {description}", prefix = prefix, width = max_length, description = arg_d.description());
+            for (i, arg_d) in arg_entries.iter().enumerate() {
+                let prefix = argument_prefix(i, arg_d);
+                writeln!(out, "  {prefix:<width$} | {description}", prefix = prefix, width = max_length, description = arg_d.description())?;
             }
         }
 
-        println!();
+        writeln!(out)?;
+
+        Ok(())
+    }
+}
+
+/// Format the description of an option help entry.
+fn option_description(descriptor: &option::Descriptor) -> String {
+    let mut description = descriptor.description().to_string();
+
+    if let Some(variable) = descriptor.env() {
+        description.push_str(&format!(" [env: {}]", variable));
+    }
+    if descriptor.is_required() {
+        description.push_str(" (required)");
+    }
+
+    description
+}
+
+/// Format the prefix of an argument help entry (for example `<SOURCE>...` for named
+/// or `1. <string>...` for unnamed arguments).
+fn argument_prefix(index: usize, descriptor: &arg::Descriptor) -> String {
+    let value = match descriptor.name() {
+        Some(name) => format!("<{name}>", name = name.to_uppercase()),
+        None => format!("<{type_name}>", type_name = descriptor.value_type()),
+    };
+
+    let usage = match descriptor.arity() {
+        arg::Arity::Single => value,
+        arg::Arity::Optional { default: _ } => format!("[{}]", value),
+        arg::Arity::Range { min: 0, max: _ } => format!("[{}...]", value),
+        arg::Arity::Range { min: _, max: _ } => format!("{}...", value),
+    };
+
+    match descriptor.name() {
+        Some(_) => usage,
+        None => format!("{num}. {usage}", num = index + 1, usage = usage),
+    }
+}
+
+/// Format the prefix of an option help entry (for example `-f, --file (--input) <string>`).
+fn option_prefix(name: &str, descriptor: &option::Descriptor) -> String {
+    let short_name = match descriptor.short_name() {
+        Some(short_name) => format!("-{}, ", short_name),
+        None => String::from("    "),
+    };
+
+    // Negatable flags are rendered as `--[no-]name`
+    let negation = if descriptor.is_negatable() { "[no-]" } else { "" };
+
+    let mut aliases: Vec<String> = descriptor.get_aliases().iter().map(|s| format!("--{}{}", negation, s)).collect();
+    aliases.sort();
+
+    // Counted flags are repeatable and flags take no value argument
+    let value_hint = match descriptor.value_type() {
+        option::Type::Count { default: _ } => String::from("..."),
+        option::Type::Bool { default: _ } => String::new(),
+        value_type => format!(" <{}>", value_type),
+    };
+
+    if aliases.is_empty() {
+        format!("{short_name}--{negation}{name}{value_hint}", short_name = short_name, negation = negation, name = name, value_hint = value_hint)
+    } else {
+        format!(
+            "{short_name}--{negation}{name} ({aliases}){value_hint}",
+            short_name = short_name,
+            negation = negation,
+            name = name,
+            aliases = aliases.join(", "),
+            value_hint = value_hint
+        )
     }
 }
@@ -1,17 +1,31 @@
+
+mod config;
 mod error;
 mod group;
 mod help;
+mod matches;
+mod response_file;
+mod suggestion;
 
 pub mod arg;
+pub mod environment;
 pub mod option;
 pub mod parser;
+pub mod repl;
 
-pub use group::Group;
-pub use help::{HelpEntry, HelpPrinter};
+pub use error::{CommandError, CommandResult, ParserError, RunError};
+pub use group::{Consumer, Group, SyncConsumer, SyncGroup};
+pub use help::{HelpEntry, HelpPrinter, OptionEntry, SubcommandEntry};
+pub use matches::Matches;
 
 #[cfg(test)]
 mod tests {
-    use crate::{Group, option, arg, parser};
+    use crate::{CommandError, Group, HelpPrinter, OptionEntry, ParserError, RunError, SubcommandEntry, SyncConsumer, option, arg, parser, repl};
+    use crate::parser::{ParseOptions, OptionSyntax, NegativeNumberPolicy};
+    use std::collections::HashMap;
+    use std::path::PathBuf;
+    use std::sync::Arc;
+    use std::{env, fs};
 
     #[test]
     fn simple() {
@@ -32,6 +46,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::assertions_on_constants)]
     fn group_alias_test() {
         let group = Group::new(Box::new(|_, _| {
             assert!(false);
@@ -46,6 +61,1038 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[test]
+    fn end_of_options_terminator() {
+        let group = Group::new(Box::new(|args, options| {
+            assert_eq!(args[0].str().unwrap(), "-rf");
+            assert_eq!(args[1].str().unwrap(), "--weird");
+            assert_eq!(args.trailing(), &vec!(String::from("-rf"), String::from("--weird")));
+
+            assert!(options.get("force").unwrap().bool().unwrap());
+        }), "Group taking positional arguments starting with '-'")
+            .add_option(option::Descriptor::new("force", option::Type::Bool { default: false }, "Force it"))
+            .add_argument(arg::Descriptor::new(arg::Type::Str, "First"))
+            .add_argument(arg::Descriptor::new(arg::Type::Str, "Second"))
+            .add_child("test", None, Group::new(Box::new(|_, _| {
+                panic!("Sub-command after '--' must not be entered");
+            }), "Sub-command"));
+
+        let args: Vec<&str> = vec!("dummy.exe", "--force", "--", "-rf", "--weird");
+        assert!(parser::parse_from(group, &args[..], None).is_ok());
+    }
+
+    #[test]
+    fn end_of_options_terminator_stops_subcommand_lookup() {
+        let group = Group::new(Box::new(|args, _| {
+            assert_eq!(args[0].str().unwrap(), "test");
+        }), "Root group")
+            .add_argument(arg::Descriptor::new(arg::Type::Str, "Name"))
+            .add_child("test", None, Group::new(Box::new(|_, _| {
+                panic!("Sub-command after '--' must not be entered");
+            }), "Sub-command"));
+
+        let args: Vec<&str> = vec!("dummy.exe", "--", "test");
+        assert!(parser::parse_from(group, &args[..], None).is_ok());
+    }
+
+    fn short_option_group() -> Group {
+        Group::new(Box::new(|args, options| {
+            assert!(options.get("verbose").unwrap().bool().unwrap());
+            assert!(options.get("extract").unwrap().bool().unwrap());
+            assert_eq!(options.get("file").unwrap().str().unwrap(), "archive.tar");
+            assert_eq!(args.len(), 0);
+        }), "Group with short options")
+            .add_option(option::Descriptor::new("verbose", option::Type::Bool { default: false }, "Verbose output").with_short_name('v'))
+            .add_option(option::Descriptor::new("extract", option::Type::Bool { default: false }, "Extract files").with_short_name('x'))
+            .add_option(option::Descriptor::new("file", option::Type::Str { default: String::from("") }, "Archive file").with_short_name('f'))
+    }
+
+    #[test]
+    fn short_option_clustering() {
+        let args: Vec<&str> = vec!("dummy.exe", "-vxf", "archive.tar");
+        assert!(parser::parse_from(short_option_group(), &args[..], None).is_ok());
+
+        let args: Vec<&str> = vec!("dummy.exe", "-xvfarchive.tar");
+        assert!(parser::parse_from(short_option_group(), &args[..], None).is_ok());
+
+        let args: Vec<&str> = vec!("dummy.exe", "-v", "-x", "-f=archive.tar");
+        assert!(parser::parse_from(short_option_group(), &args[..], None).is_ok());
+
+        let args: Vec<&str> = vec!("dummy.exe", "--verbose", "--extract", "--file=archive.tar");
+        assert!(parser::parse_from(short_option_group(), &args[..], None).is_ok());
+    }
+
+    #[test]
+    fn explicit_boolean_flag_values() {
+        let group = || Group::new(Box::new(|args, options| {
+            assert!(!options.get("verbose").unwrap().bool().unwrap());
+            assert_eq!(args.len(), 0);
+        }), "Group with a flag")
+            .add_option(option::Descriptor::new("verbose", option::Type::Bool { default: true }, "Verbose output").with_short_name('v'))
+            .add_option(option::Descriptor::new("quiet", option::Type::Bool { default: false }, "Quiet output").with_short_name('q'));
+
+        for args in &[
+            vec!("dummy.exe", "--verbose=false"),
+            vec!("dummy.exe", "-v=false"),
+            vec!("dummy.exe", "--verbose", "false"),
+            vec!("dummy.exe", "-v", "false"),
+            vec!("dummy.exe", "-qv=false"),
+        ] {
+            assert!(parser::parse_from(group(), &args[..], None).is_ok());
+        }
+
+        // Flags within a cluster never consume the next argument
+        let args: Vec<&str> = vec!("dummy.exe", "-qv", "false");
+        let matches = parser::get_matches_from(group(), &args[..], None);
+        assert!(matches!(matches.err().unwrap(), ParserError::WrongArgumentCount { .. }));
+
+        // Flags are documented without value argument
+        let mut help: Vec<u8> = Vec::new();
+        let args: Vec<&str> = vec!("dummy.exe", "--help");
+        parser::get_matches_from(group(), &args[..], None).unwrap().write_help(None, &mut help).unwrap();
+        let help = String::from_utf8(help).unwrap();
+        assert!(help.contains("-v, --verbose "));
+        assert!(!help.contains("<boolean>"));
+    }
+
+    #[test]
+    fn short_and_long_names_are_distinguished() {
+        // Long names are only matched with '--'
+        let args: Vec<&str> = vec!("dummy.exe", "-verbose");
+        assert!(parser::parse_from(short_option_group(), &args[..], None).is_err());
+
+        // Short names are only matched with '-'
+        let args: Vec<&str> = vec!("dummy.exe", "--v", "-xf", "archive.tar");
+        assert!(parser::parse_from(short_option_group(), &args[..], None).is_err());
+    }
+
+    #[test]
+    fn legacy_any_dashes_syntax() {
+        let options = ParseOptions {
+            option_syntax: OptionSyntax::AnyDashes,
+            ..Default::default()
+        };
+
+        let args: Vec<&str> = vec!("dummy.exe", "---verbose", "-extract", "--f", "archive.tar");
+        assert!(parser::parse_from(short_option_group(), &args[..], Some(options)).is_ok());
+    }
+
+    fn negative_number_group() -> Group {
+        Group::new(Box::new(|args, options| {
+            assert_eq!(options.get("offset").unwrap().int().unwrap(), -5);
+            assert_eq!(args[0].float().unwrap(), -3.2);
+        }), "Group taking negative numbers")
+            .add_option(option::Descriptor::new("offset", option::Type::Int { default: 0 }, "Offset").with_short_name('o'))
+            .add_option(option::Descriptor::new("one", option::Type::Bool { default: false }, "Flag named like a digit").with_short_name('3'))
+            .add_argument(arg::Descriptor::new(arg::Type::Float, "Number"))
+    }
+
+    #[test]
+    fn negative_numbers_as_values() {
+        let args: Vec<&str> = vec!("dummy.exe", "--offset", "-5", "-3.2");
+        assert!(parser::parse_from(negative_number_group(), &args[..], None).is_ok());
+
+        let args: Vec<&str> = vec!("dummy.exe", "-3.2", "-o", "-5");
+        assert!(parser::parse_from(negative_number_group(), &args[..], None).is_ok());
+    }
+
+    #[test]
+    fn negative_number_policy_for_ambiguous_values() {
+        // '-3.2' is now interpreted as short option cluster '-3', '-.', ...
+        let args: Vec<&str> = vec!("dummy.exe", "--offset", "-5", "-3.2");
+
+        let options = ParseOptions {
+            negative_numbers: NegativeNumberPolicy::PreferOption,
+            ..Default::default()
+        };
+        assert!(parser::parse_from(negative_number_group(), &args[..], Some(options)).is_err());
+
+        // '-7' is no known option, thus only a value unless negative numbers are disabled
+        let group = || Group::new(Box::new(|args, _| {
+            assert_eq!(args[0].int().unwrap(), -7);
+        }), "Group taking a number")
+            .add_argument(arg::Descriptor::new(arg::Type::Int, "Number"));
+        let args: Vec<&str> = vec!("dummy.exe", "-7");
+        assert!(parser::parse_from(group(), &args[..], None).is_ok());
+
+        let options = ParseOptions {
+            negative_numbers: NegativeNumberPolicy::Disabled,
+            ..Default::default()
+        };
+        assert!(matches!(parser_error(parser::parse_from(group(), &args[..], Some(options))), ParserError::UnknownOption { .. }));
+    }
+
+    #[test]
+    fn repeatable_list_options() {
+        let group = Group::new(Box::new(|_, options| {
+            let includes: Vec<&String> = options.get("include").unwrap().list().unwrap().iter().map(|v| v.str().unwrap()).collect();
+            assert_eq!(includes, vec!("a", "b", "c"));
+
+            let levels: Vec<i32> = options.get("level").unwrap().list().unwrap().iter().map(|v| v.int().unwrap()).collect();
+            assert_eq!(levels, vec!(1, 2));
+        }), "Group with list options")
+            .add_option(option::Descriptor::new("include", option::Type::StrList { default: vec!() }, "Include paths").with_short_name('I'))
+            .add_option(option::Descriptor::new("level", option::Type::IntList { default: vec!(1, 2) }, "Levels"));
+
+        let args: Vec<&str> = vec!("dummy.exe", "--include", "a", "-Ib", "--include=c");
+        assert!(parser::parse_from(group, &args[..], None).is_ok());
+    }
+
+    #[test]
+    fn non_list_option_specified_twice() {
+        let group = Group::new(Box::new(|_, _| {
+            panic!("Consumer must not be called");
+        }), "Group")
+            .add_option(option::Descriptor::new("name", option::Type::Str { default: String::from("") }, "Name").with_short_name('n'));
+
+        let args: Vec<&str> = vec!("dummy.exe", "--name", "a", "-n", "b");
+        assert!(parser::parse_from(group, &args[..], None).is_err());
+    }
+
+    fn verbosity_group(expected_verbosity: i32) -> Group {
+        Group::new(Box::new(move |_, options| {
+            assert_eq!(options.get("verbose").unwrap().int().unwrap(), expected_verbosity);
+        }), "Group with counted flag")
+            .add_option(option::Descriptor::new("verbose", option::Type::Count { default: 0 }, "Verbosity level").with_short_name('v'))
+            .add_option(option::Descriptor::new("extract", option::Type::Bool { default: false }, "Extract files").with_short_name('x'))
+    }
+
+    #[test]
+    fn counted_flags() {
+        let args: Vec<&str> = vec!("dummy.exe");
+        assert!(parser::parse_from(verbosity_group(0), &args[..], None).is_ok());
+
+        let args: Vec<&str> = vec!("dummy.exe", "-vvv");
+        assert!(parser::parse_from(verbosity_group(3), &args[..], None).is_ok());
+
+        let args: Vec<&str> = vec!("dummy.exe", "-v", "-xv", "--verbose", "--verbose");
+        assert!(parser::parse_from(verbosity_group(4), &args[..], None).is_ok());
+
+        // The value of counted flags is the number of occurrences only
+        for args in &[vec!("dummy.exe", "--verbose=-3"), vec!("dummy.exe", "-v=5", "-v"), vec!("dummy.exe", "-xv=5")] {
+            let error = parser_error(parser::parse_from(verbosity_group(0), &args[..], None));
+            assert!(matches!(error, ParserError::UnexpectedValue { .. }), "{:?}", error);
+        }
+
+        let args: Vec<&str> = vec!("dummy.exe", "--verbose=3");
+        let error = parser_error(parser::parse_from(verbosity_group(0), &args[..], None));
+        assert_eq!(error.to_string(), "Option '--verbose' does not take a value (got '3')");
+    }
+
+    #[test]
+    fn variadic_arguments() {
+        let group = Group::new(Box::new(|args, _| {
+            let sources: Vec<&String> = args[0].list().unwrap().iter().map(|v| v.str().unwrap()).collect();
+            assert_eq!(sources, vec!("a.txt", "b.txt"));
+            assert_eq!(args[1].str().unwrap(), "dest");
+        }), "Copy files")
+            .add_argument(arg::Descriptor::new(arg::Type::Str, "Source files").one_or_more())
+            .add_argument(arg::Descriptor::new(arg::Type::Str, "Destination"));
+
+        let args: Vec<&str> = vec!("dummy.exe", "a.txt", "b.txt", "dest");
+        assert!(parser::parse_from(group, &args[..], None).is_ok());
+
+        let group = Group::new(Box::new(|_, _| {}), "Copy files")
+            .add_argument(arg::Descriptor::new(arg::Type::Str, "Source files").one_or_more())
+            .add_argument(arg::Descriptor::new(arg::Type::Str, "Destination"));
+
+        let args: Vec<&str> = vec!("dummy.exe", "dest");
+        assert!(parser::parse_from(group, &args[..], None).is_err());
+    }
+
+    #[test]
+    fn variadic_argument_in_the_middle() {
+        let group = Group::new(Box::new(|args, _| {
+            assert_eq!(args[0].str().unwrap(), "first");
+            assert_eq!(args[1].list().unwrap(), &vec!(arg::Value::Int { value: 1 }, arg::Value::Int { value: 2 }));
+            assert_eq!(args[2].str().unwrap(), "last");
+        }), "Group")
+            .add_argument(arg::Descriptor::new(arg::Type::Str, "First"))
+            .add_argument(arg::Descriptor::new(arg::Type::Int, "Numbers").with_range(0, Some(2)))
+            .add_argument(arg::Descriptor::new(arg::Type::Str, "Last"));
+
+        let args: Vec<&str> = vec!("dummy.exe", "first", "1", "2", "last");
+        assert!(parser::parse_from(group, &args[..], None).is_ok());
+    }
+
+    #[test]
+    fn optional_argument_with_default() {
+        let group = |expected: &'static str| Group::new(Box::new(move |args, _| {
+            assert_eq!(args[0].str().unwrap(), "file");
+            assert_eq!(args[1].str().unwrap(), expected);
+        }), "Group")
+            .add_argument(arg::Descriptor::new(arg::Type::Str, "File"))
+            .add_argument(arg::Descriptor::new(arg::Type::Str, "Mode").optional(arg::Value::Str { value: String::from("read") }));
+
+        let args: Vec<&str> = vec!("dummy.exe", "file");
+        assert!(parser::parse_from(group("read"), &args[..], None).is_ok());
+
+        let args: Vec<&str> = vec!("dummy.exe", "file", "write");
+        assert!(parser::parse_from(group("write"), &args[..], None).is_ok());
+
+        let args: Vec<&str> = vec!("dummy.exe", "file", "write", "too-much");
+        assert!(parser::parse_from(group("write"), &args[..], None).is_err());
+    }
+
+    #[test]
+    fn named_arguments() {
+        let group = Group::new(Box::new(|args, _| {
+            assert_eq!(args["source"].str().unwrap(), "a.txt");
+            assert_eq!(args.by_name("destination").unwrap().str().unwrap(), "b.txt");
+            assert_eq!(args[1].str().unwrap(), "b.txt");
+            assert!(args.by_name("unknown").is_none());
+        }), "Copy a file")
+            .add_argument(arg::Descriptor::new(arg::Type::Str, "Source file").with_name("source"))
+            .add_argument(arg::Descriptor::new(arg::Type::Str, "Destination file").with_name("destination"));
+
+        let args: Vec<&str> = vec!("dummy.exe", "a.txt", "b.txt");
+        assert!(parser::parse_from(group, &args[..], None).is_ok());
+    }
+
+    #[test]
+    fn required_options() {
+        let group = || Group::new(Box::new(|_, options| {
+            assert_eq!(options.get("token").unwrap().str().unwrap(), "secret");
+        }), "Group with required options")
+            .add_option(option::Descriptor::new("token", option::Type::Str { default: String::from("") }, "Token").required())
+            .add_option(option::Descriptor::new("user", option::Type::Str { default: String::from("") }, "User").required());
+
+        let args: Vec<&str> = vec!("dummy.exe", "--token", "secret", "--user", "me");
+        assert!(parser::parse_from(group(), &args[..], None).is_ok());
+
+        let args: Vec<&str> = vec!("dummy.exe");
+        let error = parser::parse_from(group(), &args[..], None).err().unwrap();
+        assert_eq!(error.to_string(), "Missing required options: '--token', '--user'");
+    }
+
+    #[test]
+    fn options_without_default() {
+        let group = |expected: Option<i32>| Group::new(Box::new(move |_, options| {
+            assert_eq!(options.get("count").map(|v| v.int().unwrap()), expected);
+        }), "Group with option without default")
+            .add_option(option::Descriptor::new("count", option::Type::Int { default: 0 }, "Count").without_default());
+
+        let args: Vec<&str> = vec!("dummy.exe");
+        assert!(parser::parse_from(group(None), &args[..], None).is_ok());
+
+        let args: Vec<&str> = vec!("dummy.exe", "--count", "0");
+        assert!(parser::parse_from(group(Some(0)), &args[..], None).is_ok());
+    }
+
+    #[test]
+    fn option_value_sources() {
+        let group = || Group::new(Box::new(|_, options| {
+            assert_eq!(options.source("verbose").unwrap(), &option::Source::CommandLine { spelling: String::from("-v") });
+            assert!(options.is_explicit("verbose"));
+
+            assert_eq!(options.source("the-truth").unwrap(), &option::Source::Default);
+            assert!(!options.is_explicit("the-truth"));
+
+            assert_eq!(options.source("name").unwrap(), &option::Source::CommandLine { spelling: String::from("--alias") });
+        }), "Group")
+            .add_option(option::Descriptor::new("verbose", option::Type::Bool { default: false }, "Verbose output").with_short_name('v'))
+            .add_option(option::Descriptor::new("the-truth", option::Type::Int { default: 42 }, "The truth about everything"))
+            .add_option(option::Descriptor::new("name", option::Type::Str { default: String::from("") }, "Name").add_alias("alias"));
+
+        let args: Vec<&str> = vec!("dummy.exe", "-v", "--alias", "test");
+        assert!(parser::parse_from(group(), &args[..], None).is_ok());
+
+        // Options are recorded as typed regardless of the number of dashes
+        let options = ParseOptions {
+            option_syntax: OptionSyntax::AnyDashes,
+            ..Default::default()
+        };
+        let args: Vec<&str> = vec!("dummy.exe", "-----verbose", "-name=test");
+        let matches = parser::get_matches_from(group(), &args[..], Some(options)).unwrap();
+        assert_eq!(matches.options().source("verbose").unwrap(), &option::Source::CommandLine { spelling: String::from("-----verbose") });
+        assert_eq!(matches.options().source("name").unwrap(), &option::Source::CommandLine { spelling: String::from("-name") });
+    }
+
+    fn environment(variables: Vec<(&str, &str)>) -> ParseOptions {
+        let environment: HashMap<String, String> = variables.into_iter().map(|(k, v)| (String::from(k), String::from(v))).collect();
+
+        ParseOptions {
+            environment: Box::new(environment),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn environment_variable_fallback() {
+        let group = |expected: i32, expected_source: option::Source| Group::new(Box::new(move |_, options| {
+            assert_eq!(options.get("port").unwrap().int().unwrap(), expected);
+            assert_eq!(options.source("port").unwrap(), &expected_source);
+        }), "Group")
+            .add_option(option::Descriptor::new("port", option::Type::Int { default: 80 }, "Port").with_env("PORT"));
+
+        let args: Vec<&str> = vec!("dummy.exe");
+        let source = option::Source::Environment { variable: String::from("PORT") };
+        assert!(parser::parse_from(group(8080, source), &args[..], Some(environment(vec!(("PORT", "8080"))))).is_ok());
+
+        let args: Vec<&str> = vec!("dummy.exe", "--port", "1234");
+        let source = option::Source::CommandLine { spelling: String::from("--port") };
+        assert!(parser::parse_from(group(1234, source), &args[..], Some(environment(vec!(("PORT", "8080"))))).is_ok());
+
+        let args: Vec<&str> = vec!("dummy.exe");
+        assert!(parser::parse_from(group(80, option::Source::Default), &args[..], Some(environment(vec!()))).is_ok());
+
+        let args: Vec<&str> = vec!("dummy.exe");
+        assert!(parser::parse_from(group(80, option::Source::Default), &args[..], Some(environment(vec!(("PORT", "abc"))))).is_err());
+    }
+
+    #[test]
+    fn automatic_environment_variable_prefix() {
+        let group = Group::new(Box::new(|_, _| {}), "Root group")
+            .add_option(option::Descriptor::new("verbose", option::Type::Bool { default: false }, "Verbose output"))
+            .add_child("remote", None, Group::new(Box::new(|_, _| {}), "Remote")
+                .add_child("add", None, Group::new(Box::new(|_, options| {
+                    assert!(options.get("verbose").unwrap().bool().unwrap());
+                    assert!(options.get("dry-run").unwrap().bool().unwrap());
+                    assert_eq!(options.get("name").unwrap().str().unwrap(), "origin");
+                }), "Add remote")
+                    .add_option(option::Descriptor::new("dry-run", option::Type::Bool { default: false }, "Dry run"))
+                    .add_option(option::Descriptor::new("name", option::Type::Str { default: String::from("") }, "Name").with_env("REMOTE_NAME"))));
+
+        let mut options = environment(vec!(("MYAPP_VERBOSE", "true"), ("MYAPP_REMOTE_ADD_DRY_RUN", "true"), ("REMOTE_NAME", "origin")));
+        options.env_prefix = Some(String::from("MYAPP_"));
+
+        let args: Vec<&str> = vec!("dummy.exe", "remote", "add");
+        assert!(parser::parse_from(group, &args[..], Some(options)).is_ok());
+    }
+
+    fn write_temp_file(name: &str, content: &str) -> PathBuf {
+        let path = env::temp_dir().join(format!("cmd-args-test-{}-{}", std::process::id(), name));
+        fs::write(&path, content).unwrap();
+
+        path
+    }
+
+    fn config_group() -> Group {
+        Group::new(Box::new(|_, _| {}), "Root group")
+            .add_option(option::Descriptor::new("verbose", option::Type::Bool { default: false }, "Verbose output"))
+            .add_option(option::Descriptor::new("name", option::Type::Str { default: String::from("default") }, "Name"))
+            .add_child("remote", None, Group::new(Box::new(|_, _| {}), "Remote")
+                .add_child("add", None, Group::new(Box::new(|_, options| {
+                    assert!(options.get("verbose").unwrap().bool().unwrap());
+                    assert_eq!(options.get("name").unwrap().str().unwrap(), "project name");
+                    assert_eq!(options.get("port").unwrap().int().unwrap(), 22);
+                    assert_eq!(options.get("tag").unwrap().list().unwrap().len(), 2);
+                    assert!(options.is_explicit("verbose"));
+                    assert!(matches!(options.source("port").unwrap(), option::Source::ConfigFile { path: _, line: 6 }));
+                }), "Add remote")
+                    .add_option(option::Descriptor::new("port", option::Type::Int { default: 0 }, "Port"))
+                    .add_option(option::Descriptor::new("tag", option::Type::StrList { default: vec!() }, "Tags"))))
+    }
+
+    #[test]
+    fn layered_config_files() {
+        let system = write_temp_file("system", "verbose = false\nname = system\n\n[remote.add]\nport = 21\n");
+        let user = write_temp_file("user", "# User configuration\n[remote]\nname = \"user name\"\n\n[remote.add]\nport = 22\n");
+        let project = write_temp_file("project", "name = 'project name' ; Overrides the user name\n[remote.add]\ntag = [\"a\", b]\n");
+
+        let options = ParseOptions {
+            config_files: vec!(system.clone(), user.clone(), project.clone(), PathBuf::from("does-not-exist.ini")),
+            ..Default::default()
+        };
+
+        let args: Vec<&str> = vec!("dummy.exe", "remote", "add", "--verbose");
+        assert!(parser::parse_from(config_group(), &args[..], Some(options)).is_ok());
+
+        for path in [system, user, project] {
+            fs::remove_file(path).unwrap();
+        }
+    }
+
+    #[test]
+    fn invalid_config_file_value() {
+        let path = write_temp_file("invalid", "[remote.add]\n\nport = abc\n");
+
+        let options = ParseOptions {
+            config_files: vec!(path.clone()),
+            ..Default::default()
+        };
+
+        let args: Vec<&str> = vec!("dummy.exe", "remote", "add");
+        let error = parser::parse_from(config_group(), &args[..], Some(options)).err().unwrap();
+        assert_eq!(error.to_string(), format!("{}:3: Expected value 'abc' of option '--port' to be of type 'integer'", path.display()));
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn response_files() {
+        let nested = write_temp_file("nested-response", "--name 'John Doe'\n");
+        let response_file = write_temp_file("response", &format!("-v \"first arg\"\n@{}\n", nested.file_name().unwrap().to_str().unwrap()));
+
+        let group = Group::new(Box::new(|args, options| {
+            assert!(options.get("verbose").unwrap().bool().unwrap());
+            assert_eq!(options.get("name").unwrap().str().unwrap(), "John Doe");
+            assert_eq!(args[0].str().unwrap(), "first arg");
+            assert_eq!(args[1].str().unwrap(), "@literal");
+        }), "Group")
+            .add_option(option::Descriptor::new("verbose", option::Type::Bool { default: false }, "Verbose output").with_short_name('v'))
+            .add_option(option::Descriptor::new("name", option::Type::Str { default: String::from("") }, "Name"))
+            .add_argument(arg::Descriptor::new(arg::Type::Str, "First"))
+            .add_argument(arg::Descriptor::new(arg::Type::Str, "Second"));
+
+        let response_file_arg = format!("@{}", response_file.display());
+        let args: Vec<&str> = vec!("dummy.exe", &response_file_arg, "@@literal");
+        let options = ParseOptions {
+            response_files: true,
+            ..Default::default()
+        };
+        assert!(parser::parse_from(group, &args[..], Some(options)).is_ok());
+
+        fs::remove_file(nested).unwrap();
+        fs::remove_file(response_file).unwrap();
+    }
+
+    #[test]
+    fn recursive_response_files() {
+        let path = env::temp_dir().join(format!("cmd-args-test-{}-recursive", std::process::id()));
+        fs::write(&path, format!("@{}", path.display())).unwrap();
+
+        let response_file_arg = format!("@{}", path.display());
+        let args: Vec<&str> = vec!("dummy.exe", &response_file_arg);
+        let options = ParseOptions {
+            response_files: true,
+            ..Default::default()
+        };
+        let error = parser::parse_from(Group::new(Box::new(|_, _| {}), "Group"), &args[..], Some(options)).err().unwrap();
+        assert!(error.to_string().contains("references itself recursively"));
+
+        fs::remove_file(path).unwrap();
+
+        let options = ParseOptions {
+            response_files: true,
+            ..Default::default()
+        };
+        let args: Vec<&str> = vec!("dummy.exe", "@does-not-exist.txt");
+        let error = parser::parse_from(Group::new(Box::new(|_, _| {}), "Group"), &args[..], Some(options)).err().unwrap();
+        assert!(error.to_string().contains("does-not-exist.txt"));
+    }
+
+    #[test]
+    fn negatable_flags() {
+        let group = |expected: bool| Group::new(Box::new(move |_, options| {
+            assert_eq!(options.get("color").unwrap().bool().unwrap(), expected);
+        }), "Group")
+            .add_option(option::Descriptor::new("color", option::Type::Bool { default: true }, "Colored output").add_alias("colour").negatable());
+
+        let args: Vec<&str> = vec!("dummy.exe");
+        assert!(parser::parse_from(group(true), &args[..], None).is_ok());
+
+        let args: Vec<&str> = vec!("dummy.exe", "--no-color");
+        assert!(parser::parse_from(group(false), &args[..], None).is_ok());
+
+        let args: Vec<&str> = vec!("dummy.exe", "--no-colour");
+        assert!(parser::parse_from(group(false), &args[..], None).is_ok());
+
+        let args: Vec<&str> = vec!("dummy.exe", "--no-color=true");
+        assert!(parser::parse_from(group(false), &args[..], None).is_err());
+    }
+
+    #[test]
+    fn negated_flag_name_collision() {
+        let group = Group::new(Box::new(|_, _| {}), "Group")
+            .add_option(option::Descriptor::new("color", option::Type::Bool { default: true }, "Colored output").negatable())
+            .add_option(option::Descriptor::new("no-color", option::Type::Bool { default: false }, "Disable colors"));
+
+        let args: Vec<&str> = vec!("dummy.exe");
+        assert!(parser::parse_from(group, &args[..], None).is_err());
+    }
+
+    #[test]
+    fn options_between_subcommands() {
+        let group = || Group::new(Box::new(|_, _| {
+            panic!("Root group consumer must not be called");
+        }), "Root group")
+            .add_option(option::Descriptor::new("verbose", option::Type::Bool { default: false }, "Verbose output").with_short_name('v'))
+            .add_option(option::Descriptor::new("config", option::Type::Str { default: String::from("") }, "Config").with_short_name('c'))
+            .add_child("remote", Some(vec!("r")), Group::new(Box::new(|_, _| {
+                panic!("Remote group consumer must not be called");
+            }), "Remote")
+                .add_option(option::Descriptor::new("name", option::Type::Str { default: String::from("") }, "Name"))
+                .add_child("add", None, Group::new(Box::new(|args, options| {
+                    assert!(options.get("verbose").unwrap().bool().unwrap());
+                    assert_eq!(options.get("config").unwrap().str().unwrap(), "remote");
+                    assert_eq!(options.get("name").unwrap().str().unwrap(), "add");
+                    assert_eq!(args[0].str().unwrap(), "url");
+                }), "Add remote")
+                    .add_argument(arg::Descriptor::new(arg::Type::Str, "URL"))));
+
+        let args: Vec<&str> = vec!("dummy.exe", "--verbose", "--config", "remote", "remote", "--name", "add", "add", "url");
+        assert!(parser::parse_from(group(), &args[..], None).is_ok());
+
+        let args: Vec<&str> = vec!("dummy.exe", "-vc", "remote", "r", "--name=add", "add", "url");
+        assert!(parser::parse_from(group(), &args[..], None).is_ok());
+
+        let args: Vec<&str> = vec!("dummy.exe", "r", "add", "url", "-v", "--name", "add", "-c", "remote");
+        assert!(parser::parse_from(group(), &args[..], None).is_ok());
+    }
+
+    fn override_group() -> Group {
+        Group::new(Box::new(|_, options| {
+            assert_eq!(options.get("level").unwrap().int().unwrap(), 1);
+            assert!(options.get("dry-run").is_some());
+        }), "Root group")
+            .add_option(option::Descriptor::new("level", option::Type::Int { default: 1 }, "Level"))
+            .add_option(option::Descriptor::new("color", option::Type::Bool { default: true }, "Colored output"))
+            .add_option(option::Descriptor::new("dry-run", option::Type::Bool { default: false }, "Dry run").local())
+            .add_child("child", None, Group::new(Box::new(|_, options| {
+                assert_eq!(options.get("level").unwrap().str().unwrap(), "high");
+                assert!(options.get("color").is_none());
+                assert!(options.get("dry-run").is_none());
+            }), "Child group")
+                .override_option(option::Descriptor::new("level", option::Type::Str { default: String::from("high") }, "Level name"))
+                .hide_option("color"))
+    }
+
+    #[test]
+    fn overriding_inherited_options() {
+        let args: Vec<&str> = vec!("dummy.exe");
+        assert!(parser::parse_from(override_group(), &args[..], None).is_ok());
+
+        let args: Vec<&str> = vec!("dummy.exe", "child");
+        assert!(parser::parse_from(override_group(), &args[..], None).is_ok());
+
+        // Hidden and local options are unknown in the child group
+        let args: Vec<&str> = vec!("dummy.exe", "child", "--color");
+        assert!(parser::parse_from(override_group(), &args[..], None).is_err());
+
+        let args: Vec<&str> = vec!("dummy.exe", "child", "--dry-run");
+        assert!(parser::parse_from(override_group(), &args[..], None).is_err());
+    }
+
+    #[test]
+    fn redeclaring_inherited_option_without_override() {
+        let group = Group::new(Box::new(|_, _| {}), "Root group")
+            .add_option(option::Descriptor::new("level", option::Type::Int { default: 1 }, "Level"))
+            .add_child("child", None, Group::new(Box::new(|_, _| {}), "Child group")
+                .add_option(option::Descriptor::new("level", option::Type::Int { default: 2 }, "Level")));
+
+        let args: Vec<&str> = vec!("dummy.exe", "child");
+        assert!(parser::parse_from(group, &args[..], None).is_err());
+    }
+
+    #[test]
+    fn hiding_option_not_inherited() {
+        let group = Group::new(Box::new(|_, _| {}), "Root group")
+            .add_option(option::Descriptor::new("color", option::Type::Bool { default: false }, "Colored output"))
+            .add_child("child", None, Group::new(Box::new(|_, _| {}), "Child group")
+                .hide_option("colr"));
+
+        let args: Vec<&str> = vec!("dummy.exe", "child");
+        let error = parser_error(parser::parse_from(group, &args[..], None));
+        assert!(matches!(error, ParserError::DefinitionConflict { .. }));
+        assert!(error.to_string().contains("Option 'colr' hides no inherited option"));
+    }
+
+    fn abbreviation_group() -> Group {
+        Group::new(Box::new(|_, _| {
+            panic!("Root group consumer must not be called");
+        }), "Root group")
+            .add_option(option::Descriptor::new("verbose", option::Type::Bool { default: false }, "Verbose output"))
+            .add_option(option::Descriptor::new("version", option::Type::Bool { default: false }, "Show version"))
+            .add_child("remote", None, Group::new(Box::new(|_, _| {}), "Remote")
+                .add_child("add", None, Group::new(Box::new(|_, options| {
+                    assert!(options.get("verbose").unwrap().bool().unwrap());
+                }), "Add remote"))
+                .add_child("remove", Some(vec!("rm")), Group::new(Box::new(|_, _| {}), "Remove remote")))
+            .add_child("rename", None, Group::new(Box::new(|_, _| {}), "Rename"))
+    }
+
+    #[test]
+    fn unique_prefix_abbreviations() {
+        let abbreviations = || Some(ParseOptions {
+            abbreviations: true,
+            ..Default::default()
+        });
+
+        let args: Vec<&str> = vec!("dummy.exe", "rem", "ad", "--verb");
+        assert!(parser::parse_from(abbreviation_group(), &args[..], abbreviations()).is_ok());
+
+        // Abbreviations are disabled by default
+        assert!(parser::parse_from(abbreviation_group(), &args[..], None).is_err());
+
+        let args: Vec<&str> = vec!("dummy.exe", "re");
+        let error = parser::parse_from(abbreviation_group(), &args[..], abbreviations()).err().unwrap();
+        assert_eq!(error.to_string(), "Sub-command 're' is ambiguous, candidates are: 'remote', 'rename'");
+
+        let args: Vec<&str> = vec!("dummy.exe", "remote", "add", "--ver");
+        let error = parser::parse_from(abbreviation_group(), &args[..], abbreviations()).err().unwrap();
+        assert_eq!(error.to_string(), "Option '--ver' is ambiguous, candidates are: '--verbose', '--version'");
+    }
+
+    #[test]
+    fn suggestions_for_unknown_names() {
+        let error = |args: Vec<&str>| parser::parse_from(abbreviation_group(), &args[..], None).err().unwrap().to_string();
+
+        assert_eq!(error(vec!("dummy.exe", "--verbsoe")), "Option '--verbsoe' is unknown in the command context, did you mean '--verbose'?");
+        assert_eq!(error(vec!("dummy.exe", "--versoin")), "Option '--versoin' is unknown in the command context, did you mean '--version'?");
+        assert_eq!(error(vec!("dummy.exe", "-verbose")), "Option '-v' is unknown in the command context, did you mean '--verbose'?");
+        assert_eq!(error(vec!("dummy.exe", "--quiet")), "Option '--quiet' is unknown in the command context");
+
+        assert_eq!(error(vec!("dummy.exe", "remote", "ad")), "Sub-command 'ad' is unknown, did you mean 'add'?");
+        assert_eq!(error(vec!("dummy.exe", "remote", "mr")), "Sub-command 'mr' is unknown, did you mean 'rm'?");
+        assert_eq!(error(vec!("dummy.exe", "remote", "list")), "Sub-command 'list' is unknown");
+    }
+
+    fn parser_error(result: Result<(), RunError>) -> ParserError {
+        match result {
+            Err(RunError::Parser(e)) => e,
+            _ => panic!("Expected parser error"),
+        }
+    }
+
+    #[test]
+    fn structured_errors() {
+        let args: Vec<&str> = vec!("dummy.exe", "--verbose", "remote", "add", "--verbsoe");
+        let error = parser_error(parser::parse_from(abbreviation_group(), &args[..], None));
+        assert_eq!(error, ParserError::UnknownOption {
+            token: String::from("--verbsoe"),
+            index: Some(4),
+            suggestions: vec!(String::from("--verbose")),
+            group_path: vec!(String::from("remote"), String::from("add")),
+        });
+
+        let group = Group::new(Box::new(|_, _| {}), "Group")
+            .add_option(option::Descriptor::new("port", option::Type::Int { default: 80 }, "Port"))
+            .add_argument(arg::Descriptor::new(arg::Type::Int, "Count"));
+
+        let args: Vec<&str> = vec!("dummy.exe", "5", "--port");
+        let error = parser_error(parser::parse_from(group, &args[..], None));
+        assert_eq!(error, ParserError::MissingValue {
+            option: String::from("--port"),
+            index: Some(2),
+            expected: option::Type::Int { default: 80 },
+            group_path: Vec::new(),
+        });
+        assert_eq!(error.index(), Some(2));
+    }
+
+    #[test]
+    fn collect_errors() {
+        let group = || Group::new(Box::new(|_, _| {
+            panic!("Consumer must not be called");
+        }), "Group")
+            .add_option(option::Descriptor::new("port", option::Type::Int { default: 80 }, "Port"))
+            .add_option(option::Descriptor::new("token", option::Type::Str { default: String::new() }, "Token").required())
+            .add_argument(arg::Descriptor::new(arg::Type::Int, "Count"));
+
+        let args: Vec<&str> = vec!("dummy.exe", "--unknown", "many", "--port", "abc");
+
+        // Parsing aborts on the first error by default
+        let error = parser_error(parser::parse_from(group(), &args[..], None));
+        assert_eq!(error.index(), Some(1));
+
+        let options = ParseOptions {
+            collect_errors: true,
+            ..Default::default()
+        };
+        let error = parser_error(parser::parse_from(group(), &args[..], Some(options)));
+        match &error {
+            ParserError::Multiple { errors } => {
+                let indices: Vec<Option<usize>> = errors.iter().map(|e| e.index()).collect();
+                assert_eq!(indices, vec!(Some(1), Some(2), Some(3), None));
+            }
+            _ => panic!("Expected multiple errors"),
+        }
+        assert_eq!(error.to_string(), "Option '--unknown' is unknown in the command context\n\
+            Expected argument 'many' at position 1 to be of type 'integer'\n\
+            Expected value 'abc' of option '--port' to be of type 'integer'\n\
+            Missing required options: '--token'");
+
+        // Mistyped sub-commands are detected regardless of collecting errors
+        let group = || Group::new(Box::new(|_, _| {}), "Group")
+            .add_argument(arg::Descriptor::new(arg::Type::Int, "Count"))
+            .add_child("add", None, Group::new(Box::new(|_, _| {}), "Add"));
+        let args: Vec<&str> = vec!("dummy.exe", "ad");
+        for collect_errors in &[false, true] {
+            let options = ParseOptions {
+                collect_errors: *collect_errors,
+                ..Default::default()
+            };
+            let error = parser_error(parser::parse_from(group(), &args[..], Some(options)));
+            assert!(matches!(&error, ParserError::UnknownSubCommand { suggestions, .. } if suggestions == &vec!(String::from("add"))), "{:?}", error);
+        }
+    }
+
+    #[test]
+    fn fallible_consumers() {
+        let group = || Group::new_fallible(Box::new(|args, _| {
+            let value: i32 = args[0].str().unwrap().parse()?;
+            if value < 0 {
+                return Err(CommandError::new("Value must not be negative").with_exit_code(3));
+            }
+
+            Ok(())
+        }), "Group")
+            .add_argument(arg::Descriptor::new(arg::Type::Str, "Value"));
+
+        let args: Vec<&str> = vec!("dummy.exe", "42");
+        assert!(parser::parse_from(group(), &args[..], None).is_ok());
+
+        let args: Vec<&str> = vec!("dummy.exe", "--", "-1");
+        let error = parser::parse_from(group(), &args[..], None).err().unwrap();
+        assert!(matches!(error, RunError::Command(_)));
+        assert_eq!(error.exit_code(), 3);
+        assert_eq!(error.to_string(), "Value must not be negative");
+
+        let args: Vec<&str> = vec!("dummy.exe", "abc");
+        let error = parser::parse_from(group(), &args[..], None).err().unwrap();
+        assert_eq!(error.exit_code(), 1);
+
+        let args: Vec<&str> = vec!("dummy.exe", "--unknown", "42");
+        let error = parser::parse_from(group(), &args[..], None).err().unwrap();
+        assert!(matches!(error, RunError::Parser(_)));
+        assert_eq!(error.exit_code(), 2);
+    }
+
+    #[test]
+    fn matches_without_invoking_consumers() {
+        let group = || Group::new(Box::new(|_, _| {
+            panic!("Root group consumer must not be called");
+        }), "Root group")
+            .add_option(option::Descriptor::new("verbose", option::Type::Bool { default: false }, "Verbose output"))
+            .add_child("remote", None, Group::new_fallible(Box::new(|args, _| {
+                Err(CommandError::new(format!("Cannot add remote '{}'", args[0])))
+            }), "Remote")
+                .add_argument(arg::Descriptor::new(arg::Type::Str, "Name"))
+                .add_argument(arg::Descriptor::new(arg::Type::Str, "Command").zero_or_more()));
+
+        let args: Vec<&str> = vec!("dummy.exe", "--verbose", "remote", "origin", "--", "-x");
+        let matches = parser::get_matches_from(group(), &args[..], None).unwrap();
+        assert_eq!(matches.path(), &["remote"]);
+        assert_eq!(matches.group().description(), "Remote");
+        assert_eq!(matches.args()[0].str().unwrap(), "origin");
+        assert_eq!(matches.trailing(), &["-x"]);
+        assert!(matches.options().get("verbose").unwrap().bool().unwrap());
+        assert!(matches.options().is_explicit("verbose"));
+        assert!(!matches.is_help_requested());
+        assert_eq!(matches.run().err().unwrap().to_string(), "Cannot add remote 'origin'");
+
+        let args: Vec<&str> = vec!("dummy.exe", "remote", "--help");
+        let matches = parser::get_matches_from(group(), &args[..], None).unwrap();
+        assert!(matches.is_help_requested());
+        assert!(matches.args().is_empty());
+    }
+
+    #[test]
+    fn consumers_with_context() {
+        struct Store {
+            items: Vec<String>,
+        }
+
+        let group = || Group::with_context(Box::new(|_: &mut Store, _, _| Ok(())), "Store")
+            .add_child("add", None, Group::with_context(Box::new(|store: &mut Store, args, _| {
+                store.items.push(args[0].str().unwrap().to_string());
+                Ok(())
+            }), "Add item")
+                .add_argument(arg::Descriptor::new(arg::Type::Str, "Item")))
+            .add_child("clear", None, Group::with_context(Box::new(|store: &mut Store, _, _| {
+                if store.items.is_empty() {
+                    return Err(CommandError::new("Store is empty already"));
+                }
+
+                store.items.clear();
+                Ok(())
+            }), "Clear items"));
+
+        let mut store = Store { items: Vec::new() };
+
+        let args: Vec<&str> = vec!("dummy.exe", "add", "apple");
+        assert!(parser::parse_from_with(group(), &mut store, &args[..], None).is_ok());
+        let args: Vec<&str> = vec!("dummy.exe", "add", "pear");
+        assert!(parser::parse_from_with(group(), &mut store, &args[..], None).is_ok());
+        assert_eq!(store.items, vec!("apple", "pear"));
+
+        let args: Vec<&str> = vec!("dummy.exe", "clear");
+        let matches = parser::get_matches_from(group(), &args[..], None).unwrap();
+        assert!(matches.run_with(&mut store).is_ok());
+        assert!(matches.run_with(&mut store).is_err());
+    }
+
+    #[test]
+    fn reusable_parser() {
+        let group = Arc::new(Group::with_context_sync(Box::new(|count: &mut i32, _, options| {
+            *count += options.get("step").unwrap().int().unwrap();
+            Ok(())
+        }), "Counter")
+            .add_option(option::Descriptor::new("step", option::Type::Int { default: 1 }, "Step to count by"))
+            .add_child("reset", None, Group::with_context_sync(Box::new(|count: &mut i32, _, _| {
+                *count = 0;
+                Ok(())
+            }), "Reset counter")));
+
+        let parser = parser::Parser::new(Arc::clone(&group), ParseOptions::default());
+        let mut count = 0;
+        for args in &[vec!("dummy.exe"), vec!("dummy.exe", "--step", "5"), vec!("dummy.exe", "--step=2")] {
+            assert!(parser.parse_from_with(&mut count, &args[..]).is_ok());
+        }
+        assert_eq!(count, 8);
+
+        let args: Vec<&str> = vec!("dummy.exe", "reset", "--step", "3");
+        let matches = parser.get_matches_from(&args[..]).unwrap();
+        assert_eq!(matches.path(), &["reset"]);
+        assert!(matches.run_with(&mut count).is_ok());
+        assert_eq!(count, 0);
+
+        let args: Vec<&str> = vec!("dummy.exe", "--step", "x");
+        assert!(matches!(parser.get_matches_from(&args[..]).err().unwrap(), ParserError::InvalidValue { .. }));
+        assert!(parser.parse_from_with(&mut count, &["dummy.exe", "reset"]).is_ok());
+
+        // The group is still usable by other parsers
+        assert_eq!(parser::Parser::new(group, ParseOptions::default()).get_matches_from(&["dummy.exe"]).unwrap().path().len(), 0);
+
+        // Groups held by the caller may be borrowed instead
+        struct App {
+            commands: Group<Vec<String>>,
+        }
+
+        let app = App {
+            commands: Group::with_context(Box::new(|_: &mut Vec<String>, _, _| Ok(())), "App")
+                .add_child("open", Some(vec!("o")), Group::with_context(Box::new(|opened: &mut Vec<String>, args, _| {
+                    opened.push(args[0].str().unwrap().to_string());
+                    Ok(())
+                }), "Open file")
+                    .add_argument(arg::Descriptor::new(arg::Type::Str, "File"))),
+        };
+
+        let mut opened = Vec::new();
+        let parser = parser::Parser::borrowed(&app.commands, ParseOptions::default());
+        assert!(parser.parse_from_with(&mut opened, &["dummy.exe", "open", "a.txt"]).is_ok());
+        assert!(parser.parse_from_with(&mut opened, &["dummy.exe", "o", "b.txt"]).is_ok());
+        assert_eq!(parser.get_matches_from(&["dummy.exe", "o", "c.txt"]).unwrap().path(), &["open"]);
+        assert_eq!(opened, vec!("a.txt", "b.txt"));
+        assert_eq!(app.commands.description(), "App");
+    }
+
+    #[test]
+    fn repl_sessions() {
+        let group = Group::with_context(Box::new(|_: &mut Vec<String>, _, _| Ok(())), "Admin tool")
+            .add_child("greet", None, Group::with_context(Box::new(|greetings: &mut Vec<String>, args, options| {
+                let name = args[0].str().unwrap();
+                if name.is_empty() {
+                    return Err(CommandError::new("Nobody to greet"));
+                }
+
+                let greeting = options.get("greeting").unwrap().str().unwrap();
+                greetings.push(format!("{} {}", greeting, name));
+                Ok(())
+            }), "Greet someone")
+                .add_option(option::Descriptor::new("greeting", option::Type::Str { default: String::from("Hello") }, "Greeting to use"))
+                .add_argument(arg::Descriptor::new(arg::Type::Str, "Name")));
+
+        let input = "greet World\n\n  greet --greeting 'Good morning' \"Jane Doe\"  \ngreet ''\ngreet\nunknown\nhelp greet\nhistory\nexit\ngreet Again\n";
+        let mut output: Vec<u8> = Vec::new();
+        let mut greetings = Vec::new();
+        let mut shell = repl::Repl::new(group, ParseOptions::default()).with_prompt("admin> ");
+        shell.run_with(&mut greetings, input.as_bytes(), &mut output).unwrap();
+
+        assert_eq!(greetings, vec!("Hello World", "Good morning Jane Doe"));
+        assert_eq!(shell.history().len(), 8);
+        assert_eq!(shell.history()[1], "greet --greeting 'Good morning' \"Jane Doe\"");
+
+        let output = String::from_utf8(output).unwrap();
+        assert_eq!(output.matches("admin> ").count(), 9);
+        assert!(output.contains("Nobody to greet"));
+        assert!(output.contains("Expected to have 1 arguments but got 0"));
+        assert!(output.contains("Sub-command 'unknown' is unknown"));
+        assert!(output.contains("Greet someone\n"));
+        assert!(output.contains("--greeting"));
+        assert!(output.contains("   7  history\n"));
+    }
+
+    #[test]
+    fn repl_help_with_custom_printer() {
+        struct NamesPrinter;
+
+        impl HelpPrinter for NamesPrinter {
+            fn write(
+                &self,
+                out: &mut dyn std::io::Write,
+                group: &Group,
+                subcommand_entries: &[SubcommandEntry],
+                _: &[OptionEntry],
+                _: &[arg::Descriptor],
+            ) -> std::io::Result<()> {
+                writeln!(out, "Usage of '{}'", group.description())?;
+                for entry in subcommand_entries {
+                    writeln!(out, "* {}", entry.key)?;
+                }
+                Ok(())
+            }
+        }
+
+        let group = Group::new(Box::new(|_, _| {}), "Admin tool")
+            .add_child("status", None, Group::new(Box::new(|_, _| {}), "Show the status"));
+
+        let options: ParseOptions = ParseOptions {
+            help_printer: Some(Box::new(NamesPrinter)),
+            ..ParseOptions::default()
+        };
+        let mut output: Vec<u8> = Vec::new();
+        let mut shell = repl::Repl::new(group, options).with_prompt("");
+        shell.run("help\nhelp status\n".as_bytes(), &mut output).unwrap();
+
+        assert_eq!(String::from_utf8(output).unwrap(), "Usage of 'Admin tool'\n* status\nUsage of 'Show the status'\n");
+    }
+
+    #[test]
+    fn thread_safe_command_trees() {
+        use std::sync::atomic::{AtomicI32, Ordering};
+        use std::sync::OnceLock;
+        use std::thread;
+
+        static TOTAL: AtomicI32 = AtomicI32::new(0);
+        static PARSER: OnceLock<parser::Parser<(), SyncConsumer>> = OnceLock::new();
+
+        let parser = PARSER.get_or_init(|| parser::Parser::new(Group::new_sync(Box::new(|_, _| {}), "Root")
+            .add_child("add", None, Group::new_sync(Box::new(|args, _| {
+                TOTAL.fetch_add(args[0].int().unwrap(), Ordering::SeqCst);
+            }), "Add to total")
+                .add_argument(arg::Descriptor::new(arg::Type::Int, "Amount"))), ParseOptions::default()));
+
+        let workers: Vec<_> = (1..=4).map(|amount| {
+            thread::spawn(move || {
+                let amount = amount.to_string();
+                for _ in 0..10 {
+                    parser.parse_from(&["dummy.exe", "add", &amount]).unwrap();
+                }
+            })
+        }).collect();
+        for worker in workers {
+            worker.join().unwrap();
+        }
+
+        assert_eq!(TOTAL.load(Ordering::SeqCst), 100);
+
+        // Consumers and parse options of other groups need not be thread-safe
+        struct SharedEnvironment(std::rc::Rc<std::cell::RefCell<HashMap<String, String>>>);
+
+        impl crate::environment::Environment for SharedEnvironment {
+            fn var(&self, name: &str) -> Option<String> {
+                self.0.borrow().get(name).cloned()
+            }
+        }
+
+        let count = std::rc::Rc::new(std::cell::Cell::new(0));
+        let counter = std::rc::Rc::clone(&count);
+        let group = Group::new(Box::new(move |_, options| counter.set(counter.get() + options.get("amount").unwrap().int().unwrap())), "Local group")
+            .add_option(option::Descriptor::new("amount", option::Type::Int { default: 1 }, "Amount").with_env("AMOUNT"));
+        let variables = std::rc::Rc::new(std::cell::RefCell::new(HashMap::new()));
+        let options: ParseOptions = ParseOptions {
+            environment: Box::new(SharedEnvironment(std::rc::Rc::clone(&variables))),
+            ..ParseOptions::default()
+        };
+        let parser = parser::Parser::new(group, options);
+        assert!(parser.parse_from(&["dummy.exe"]).is_ok());
+        variables.borrow_mut().insert(String::from("AMOUNT"), String::from("5"));
+        assert!(parser.parse_from(&["dummy.exe"]).is_ok());
+        assert_eq!(count.get(), 6);
+    }
+
     // TODO
     // - Test help output
     // - Test nested groups
@@ -0,0 +1,103 @@
+use std::sync::Arc;
+use std::io;
+use crate::{Consumer, Group, HelpPrinter, CommandResult, option, arg};
+use crate::parser::{self, CommandContext};
+
+/// Result of parsing the command line: the resolved command context along with the parsed
+/// argument and option values, without the consumer of the command having been invoked.
+/// Matches borrow the root group for the lifetime `'g` when parsed by a borrowing parser.
+pub struct Matches<'g, C = (), F: ?Sized = Consumer<C>> {
+    /// Command context (group and anticipated options) addressed by the command line.
+    context: Arc<CommandContext<'g, C, F>>,
+
+    /// Names of the groups on the path from the root group to the context group.
+    path: Vec<String>,
+
+    /// Parsed argument values (including the raw arguments following `--`).
+    args: arg::Values,
+
+    /// Parsed option values along with their sources.
+    options: option::Values,
+
+    /// Whether the help documentation has been requested (via `--help` or `-?`).
+    help_requested: bool,
+}
+
+impl<F> Matches<'_, (), F> where F: ?Sized + Fn(&mut (), &arg::Values, &option::Values) -> CommandResult {
+    /// Invoke the consumer of the command context with the parsed values.
+    pub fn run(&self) -> CommandResult {
+        self.run_with(&mut ())
+    }
+}
+
+impl<'g, C, F: ?Sized> Matches<'g, C, F> {
+    /// Create new matches.
+    pub(crate) fn new(
+        context: Arc<CommandContext<'g, C, F>>,
+        path: Vec<String>,
+        args: arg::Values,
+        options: option::Values,
+        help_requested: bool,
+    ) -> Self {
+        Matches {
+            context,
+            path,
+            args,
+            options,
+            help_requested,
+        }
+    }
+
+    /// Get the group providing the command context.
+    pub fn group(&self) -> &Group<C, F> {
+        &self.context.group
+    }
+
+    /// Get the names of the groups on the path from the root group to the context group
+    /// (for example `["remote", "add"]` for `mytool remote add`).
+    pub fn path(&self) -> &[String] {
+        &self.path
+    }
+
+    /// Get the parsed argument values.
+    /// When the help documentation has been requested, arguments are not parsed at all.
+    pub fn args(&self) -> &arg::Values {
+        &self.args
+    }
+
+    /// Get the parsed option values along with their sources.
+    pub fn options(&self) -> &option::Values {
+        &self.options
+    }
+
+    /// Get the raw arguments following the end-of-options terminator `--`.
+    pub fn trailing(&self) -> &[String] {
+        self.args.trailing()
+    }
+
+    /// Check whether the help documentation has been requested (via `--help` or `-?`).
+    /// Required options and arguments are not checked in this case.
+    pub fn is_help_requested(&self) -> bool {
+        self.help_requested
+    }
+
+    /// Print the help documentation of the command context using the passed help printer
+    /// (or the default one).
+    pub fn print_help(&self, help_printer: Option<&dyn HelpPrinter<C, F>>) {
+        let group = &self.context.group;
+        parser::show_help(group, &self.context.options, group.get_arguments(), help_printer);
+    }
+
+    /// Write the help documentation of the command context to the passed writer using the passed
+    /// help printer (or the default one).
+    pub fn write_help(&self, help_printer: Option<&dyn HelpPrinter<C, F>>, out: &mut dyn io::Write) -> io::Result<()> {
+        let group = &self.context.group;
+        parser::write_help(group, &self.context.options, group.get_arguments(), help_printer, out)
+    }
+
+    /// Invoke the consumer of the command context with the passed context and the parsed values.
+    pub fn run_with(&self, context: &mut C) -> CommandResult
+        where F: Fn(&mut C, &arg::Values, &option::Values) -> CommandResult {
+        self.context.group.get_consumer()(context, &self.args, &self.options)
+    }
+}
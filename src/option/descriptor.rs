@@ -1,23 +1,36 @@
-use std::rc::Rc;
+use std::sync::Arc;
 use crate::option;
 use std::collections::HashSet;
 
 /// Description of an option.
+#[derive(Clone)]
 pub struct Descriptor {
-    name: Rc<String>,
+    name: Arc<String>,
+    short_name: Option<char>,
     aliases: HashSet<String>,
     value_type: option::Type,
     description: String,
+    required: bool,
+    has_default: bool,
+    env: Option<String>,
+    negatable: bool,
+    local: bool,
 }
 
 impl Descriptor {
     /// Create a new option descriptor.
     pub fn new(name: &str, value_type: option::Type, description: &str) -> Self {
         Descriptor {
-            name: Rc::new(String::from(name)),
+            name: Arc::new(String::from(name)),
+            short_name: None,
             aliases: HashSet::new(),
             value_type,
             description: String::from(description),
+            required: false,
+            has_default: true,
+            env: None,
+            negatable: false,
+            local: false,
         }
     }
 
@@ -27,8 +40,8 @@ impl Descriptor {
     }
 
     /// Take a reference to the name of the option.
-    pub fn take_name(&self) -> Rc<String> {
-        Rc::clone(&self.name)
+    pub fn take_name(&self) -> Arc<String> {
+        Arc::clone(&self.name)
     }
 
     /// Get the type of the option value.
@@ -48,6 +61,85 @@ impl Descriptor {
         self
     }
 
+    /// Set the single-character short name of the option (for example `v` for `-v`).
+    pub fn with_short_name(mut self, short_name: char) -> Self {
+        self.short_name = Some(short_name);
+
+        self
+    }
+
+    /// Get the short name of the option (if any).
+    pub fn short_name(&self) -> Option<char> {
+        self.short_name
+    }
+
+    /// Mark the option as required, thus parsing fails when it is not specified.
+    /// The default value of the option type is ignored.
+    pub fn required(mut self) -> Self {
+        self.required = true;
+        self.has_default = false;
+
+        self
+    }
+
+    /// Check whether the option is required.
+    pub fn is_required(&self) -> bool {
+        self.required
+    }
+
+    /// Ignore the default value of the option type, so that the option is absent from
+    /// the parsed option values when not specified.
+    pub fn without_default(mut self) -> Self {
+        self.has_default = false;
+
+        self
+    }
+
+    /// Check whether the option falls back to the default value of its type when not specified.
+    pub fn has_default(&self) -> bool {
+        self.has_default
+    }
+
+    /// Bind the option to the environment variable with the passed name.
+    /// The variable is consulted when the option is not specified on the command line.
+    pub fn with_env(mut self, variable: &str) -> Self {
+        self.env = Some(String::from(variable));
+
+        self
+    }
+
+    /// Get the name of the environment variable the option is bound to (if any).
+    pub fn env(&self) -> Option<&String> {
+        self.env.as_ref()
+    }
+
+    /// Make the boolean option negatable, so that `--no-<name>` sets it to `false`.
+    /// Panics if the option is not of boolean type.
+    pub fn negatable(mut self) -> Self {
+        assert!(matches!(self.value_type, option::Type::Bool { default: _ }));
+
+        self.negatable = true;
+
+        self
+    }
+
+    /// Check whether the option is negatable via `--no-<name>`.
+    pub fn is_negatable(&self) -> bool {
+        self.negatable
+    }
+
+    /// Mark the option as local to the group it is added to, thus it is not inherited by child groups.
+    pub fn local(mut self) -> Self {
+        self.local = true;
+
+        self
+    }
+
+    /// Check whether the option is local to the group it is added to.
+    pub fn is_local(&self) -> bool {
+        self.local
+    }
+
     /// Get aliases.
     pub fn get_aliases(&self) -> &HashSet<String> {
         &self.aliases
@@ -1,7 +1,11 @@
 mod descriptor;
 mod option_type;
+mod source;
 mod value;
+mod values;
 
 pub use value::Value;
+pub use values::Values;
+pub use source::Source;
 pub use option_type::Type;
 pub use descriptor::Descriptor;
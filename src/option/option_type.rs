@@ -1,11 +1,41 @@
 use std::fmt;
 
 /// Possible types for a CLI option.
+#[derive(Debug, Clone, PartialEq)]
 pub enum Type {
     Bool { default: bool },
     Str { default: String },
     Int { default: i32 },
     Float { default: f64 },
+
+    /// Repeatable option collecting every occurrence into a list of strings.
+    StrList { default: Vec<String> },
+
+    /// Repeatable option collecting every occurrence into a list of integers.
+    IntList { default: Vec<i32> },
+
+    /// Repeatable option collecting every occurrence into a list of floats.
+    FloatList { default: Vec<f64> },
+
+    /// Flag counting the number of its occurrences (for example `-vvv` for a verbosity level of 3).
+    Count { default: i32 },
+}
+
+impl Type {
+    /// Check whether the type is numeric (integer or float).
+    pub fn is_numeric(&self) -> bool {
+        matches!(self, Type::Int { default: _ } | Type::Float { default: _ } | Type::IntList { default: _ } | Type::FloatList { default: _ })
+    }
+
+    /// Check whether the option is a flag that does not require a value on the command line.
+    pub fn is_flag(&self) -> bool {
+        matches!(self, Type::Bool { default: _ } | Type::Count { default: _ })
+    }
+
+    /// Check whether the option may be specified multiple times collecting all values in a list.
+    pub fn is_list(&self) -> bool {
+        matches!(self, Type::StrList { default: _ } | Type::IntList { default: _ } | Type::FloatList { default: _ })
+    }
 }
 
 impl fmt::Display for Type {
@@ -15,6 +45,10 @@ impl fmt::Display for Type {
             Type::Str { default: _ } => "string",
             Type::Int { default: _ } => "integer",
             Type::Float { default: _ } => "float",
+            Type::StrList { default: _ } => "string list",
+            Type::IntList { default: _ } => "integer list",
+            Type::FloatList { default: _ } => "float list",
+            Type::Count { default: _ } => "count",
         })
     }
 }
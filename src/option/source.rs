@@ -0,0 +1,31 @@
+use std::fmt;
+use std::path::PathBuf;
+
+/// Source an option value originates from.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Source {
+    /// Value has been specified on the command line using the passed spelling
+    /// (for example `--verbose`, `--talkative` or `-v`).
+    /// For options specified multiple times the spelling of the first occurrence is recorded.
+    CommandLine { spelling: String },
+
+    /// Value has been read from the environment variable with the passed name.
+    Environment { variable: String },
+
+    /// Value has been read from the configuration file at the passed path in the passed line.
+    ConfigFile { path: PathBuf, line: usize },
+
+    /// Value is the default value of the option type.
+    Default,
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Source::CommandLine { spelling } => write!(f, "command line ({})", spelling),
+            Source::Environment { variable } => write!(f, "environment variable {}", variable),
+            Source::ConfigFile { path, line } => write!(f, "configuration file {}:{}", path.display(), line),
+            Source::Default => write!(f, "default"),
+        }
+    }
+}
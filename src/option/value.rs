@@ -1,29 +1,45 @@
 use std::fmt;
+use crate::error::parse_literal;
 use crate::option;
 
 /// Possible values for a CLI option.
+#[derive(Debug, Clone, PartialEq)]
 pub enum Value {
     Bool { value: bool },
     Str { value: String },
     Int { value: i32 },
     Float { value: f64 },
+    List { value: Vec<Value> },
 }
 
 impl Value {
     /// Parse option value from string.
+    /// For list types the result is a list containing the single parsed value.
     pub fn parse(option_type: &option::Type, input: &str) -> crate::parser::Result<Value> {
         match option_type {
             option::Type::Bool { default: _ } => Ok(Value::Bool {
-                value: input.parse()?
+                value: parse_literal(input, option_type)?
             }),
             option::Type::Str { default: _ } => Ok(Value::Str {
                 value: String::from(input)
             }),
             option::Type::Int { default: _ } => Ok(Value::Int {
-                value: input.parse()?
+                value: parse_literal(input, option_type)?
             }),
             option::Type::Float { default: _ } => Ok(Value::Float {
-                value: input.parse()?
+                value: parse_literal(input, option_type)?
+            }),
+            option::Type::StrList { default: _ } => Ok(Value::List {
+                value: vec!(Value::Str { value: String::from(input) })
+            }),
+            option::Type::IntList { default: _ } => Ok(Value::List {
+                value: vec!(Value::Int { value: parse_literal(input, option_type)? })
+            }),
+            option::Type::FloatList { default: _ } => Ok(Value::List {
+                value: vec!(Value::Float { value: parse_literal(input, option_type)? })
+            }),
+            option::Type::Count { default: _ } => Ok(Value::Int {
+                value: parse_literal(input, option_type)?
             }),
         }
     }
@@ -43,6 +59,18 @@ impl Value {
             option::Type::Float { default } => Value::Float {
                 value: *default
             },
+            option::Type::StrList { default } => Value::List {
+                value: default.iter().map(|v| Value::Str { value: v.clone() }).collect()
+            },
+            option::Type::IntList { default } => Value::List {
+                value: default.iter().map(|v| Value::Int { value: *v }).collect()
+            },
+            option::Type::FloatList { default } => Value::List {
+                value: default.iter().map(|v| Value::Float { value: *v }).collect()
+            },
+            option::Type::Count { default } => Value::Int {
+                value: *default
+            },
         }
     }
 
@@ -77,6 +105,14 @@ impl Value {
             _ => None,
         }
     }
+
+    /// Get the list typed value holding every occurrence of the option in order.
+    pub fn list(&self) -> Option<&Vec<Value>> {
+        match self {
+            Value::List { value } => Some(value),
+            _ => None,
+        }
+    }
 }
 
 impl fmt::Display for Value {
@@ -86,6 +122,7 @@ impl fmt::Display for Value {
             Value::Str { value } => value.to_string(),
             Value::Int { value } => value.to_string(),
             Value::Float { value } => value.to_string(),
+            Value::List { value } => value.iter().map(|v| v.to_string()).collect::<Vec<String>>().join(", "),
         })
     }
 }
@@ -0,0 +1,47 @@
+use std::collections::HashMap;
+use crate::option::{Value, Source};
+
+/// Parsed option values handed to a group consumer.
+/// Each value is looked up by the option name and records the source it originates from.
+#[derive(Debug, Clone, Default)]
+pub struct Values {
+    /// Option values and their sources by option name.
+    entries: HashMap<String, (Value, Source)>,
+}
+
+impl Values {
+    /// Get the value of the option with the passed name.
+    pub fn get(&self, name: &str) -> Option<&Value> {
+        self.entries.get(name).map(|(value, _)| value)
+    }
+
+    /// Get the source the value of the option with the passed name originates from.
+    pub fn source(&self, name: &str) -> Option<&Source> {
+        self.entries.get(name).map(|(_, source)| source)
+    }
+
+    /// Check whether the option with the passed name has been specified explicitly on the command line.
+    pub fn is_explicit(&self, name: &str) -> bool {
+        matches!(self.source(name), Some(Source::CommandLine { spelling: _ }))
+    }
+
+    /// Check whether a value for the option with the passed name is present.
+    pub fn contains(&self, name: &str) -> bool {
+        self.entries.contains_key(name)
+    }
+
+    /// Iterate over all option names, values and their sources.
+    pub fn iter(&self) -> impl Iterator<Item=(&String, &Value, &Source)> {
+        self.entries.iter().map(|(name, (value, source))| (name, value, source))
+    }
+
+    /// Insert the value of the option with the passed name.
+    pub(crate) fn insert(&mut self, name: &str, value: Value, source: Source) {
+        self.entries.insert(String::from(name), (value, source));
+    }
+
+    /// Get the mutable value of the option with the passed name.
+    pub(crate) fn get_mut(&mut self, name: &str) -> Option<&mut Value> {
+        self.entries.get_mut(name).map(|(value, _)| value)
+    }
+}
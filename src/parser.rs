@@ -1,273 +1,1428 @@
 use std::collections::HashMap;
-use std::{result, env};
-use std::rc::Rc;
-use crate::error::ParserError;
-use crate::{Group, HelpEntry, HelpPrinter};
+use std::ops::Deref;
+use std::{result, env, io, process};
+use std::sync::{Arc, Mutex};
+use std::path::PathBuf;
+use crate::error::{ParserError, RunError};
+use crate::{CommandResult, Consumer, Group, HelpEntry, HelpPrinter, Matches, SyncConsumer};
 use crate::option;
 use crate::arg;
-use crate::help::DefaultHelpPrinter;
+use crate::help::{DefaultHelpPrinter, OptionEntry, SubcommandEntry};
+use crate::environment::{Environment, ProcessEnvironment};
+use crate::config::{ConfigFile, RawValue};
+use crate::response_file;
+use crate::suggestion;
 
 /// Type alias for parser results.
 pub type Result<T> = result::Result<T, ParserError>;
 
 static OPTION_PREFIX: char = '-';
+static LONG_OPTION_PREFIX: &str = "--";
 static OPTION_KEY_VALUE_SPLIT: char = '=';
+static FLAG_VALUE: &str = "true";
+static NEGATED_FLAG_VALUE: &str = "false";
+static NEGATION_PREFIX: &str = "no-";
+static COUNT_FLAG_VALUE: &str = "1";
+static END_OF_OPTIONS: &str = "--";
 static HELP_OPTION: &str = "help";
-static HELP_OPTION_ALIAS: &str = "?";
+static HELP_OPTION_SHORT_NAME: char = '?';
 
-/// Options to customize the parser.
-pub struct ParseOptions {
+/// Lookup of option descriptors by their name.
+pub(crate) type OptionDescriptors = HashMap<Arc<String>, Arc<option::Descriptor>>;
+
+/// Syntax used to recognize options on the command line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OptionSyntax {
+    /// POSIX-style options: `--name` refers to a long name or alias, while `-abc` is a cluster
+    /// of the short options `a`, `b` and `c`.
+    /// Values may be attached to short options (`-ofile` or `-o=file`).
+    Posix,
+
+    /// Legacy behaviour where any number of leading `-` chars is accepted and ignored,
+    /// thus `-?`, `--?` and `-----?` are interpreted the same.
+    /// Short names are treated as additional aliases and clustering is not supported.
+    AnyDashes,
+}
+
+/// Policy deciding how command line arguments that look like negative numbers (for example `-5`
+/// or `-3.2`) are treated.
+/// Unless disabled, they are always treated as values when no option of that name exists.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NegativeNumberPolicy {
+    /// Treat negative numbers as values whenever a numeric value is expected,
+    /// even if an option of that name exists.
+    PreferValue,
+
+    /// Treat negative numbers as options whenever an option of that name exists.
+    PreferOption,
+
+    /// Never treat negative numbers as values, thus every argument starting with `-` is an option.
+    Disabled,
+}
+
+/// Kind of the consumers of a group tree (taking a context of type `C`) deciding the help printer
+/// and environment types of the parse options, which have to be `Send + Sync` for thread-safe
+/// consumers (see `SyncGroup`), so that parsers for such groups may be shared between threads.
+pub trait ConsumerKind<C> {
+    /// Help printer type of the parse options.
+    type Printer: ?Sized + HelpPrinter<C, Self>;
+
+    /// Environment type of the parse options.
+    type Env: ?Sized + Environment;
+
+    /// Get the environment of the current process.
+    fn process_environment() -> Box<Self::Env>;
+
+    /// Get the passed help printer as plain help printer.
+    fn as_help_printer(help_printer: &Self::Printer) -> &dyn HelpPrinter<C, Self>;
+
+    /// Get the passed environment as plain environment.
+    fn as_environment(environment: &Self::Env) -> &dyn Environment;
+}
+
+impl<C> ConsumerKind<C> for Consumer<C> {
+    type Printer = dyn HelpPrinter<C, Consumer<C>>;
+    type Env = dyn Environment;
+
+    fn process_environment() -> Box<Self::Env> {
+        Box::new(ProcessEnvironment {})
+    }
+
+    fn as_help_printer(help_printer: &Self::Printer) -> &dyn HelpPrinter<C, Self> {
+        help_printer
+    }
+
+    fn as_environment(environment: &Self::Env) -> &dyn Environment {
+        environment
+    }
+}
+
+impl<C> ConsumerKind<C> for SyncConsumer<C> {
+    type Printer = dyn HelpPrinter<C, SyncConsumer<C>> + Send + Sync;
+    type Env = dyn Environment + Send + Sync;
+
+    fn process_environment() -> Box<Self::Env> {
+        Box::new(ProcessEnvironment {})
+    }
+
+    fn as_help_printer(help_printer: &Self::Printer) -> &dyn HelpPrinter<C, Self> {
+        help_printer
+    }
+
+    fn as_environment(environment: &Self::Env) -> &dyn Environment {
+        environment
+    }
+}
+
+/// Options to customize the parser (of groups with consumers of type `F` taking a context of type `C`).
+/// The help printer and environment have to be `Send + Sync` only for thread-safe groups
+/// (see `SyncParseOptions`).
+pub struct ParseOptions<C = (), F: ?Sized + ConsumerKind<C> = Consumer<C>> {
     /// Specify a custom help printer or the default one will be used.
-    pub help_printer: Option<Box<dyn HelpPrinter>>,
+    pub help_printer: Option<Box<F::Printer>>,
+
+    /// Syntax used to recognize options on the command line.
+    pub option_syntax: OptionSyntax,
+
+    /// Policy deciding how arguments looking like negative numbers are treated.
+    pub negative_numbers: NegativeNumberPolicy,
+
+    /// Environment consulted for options bound to environment variables.
+    pub environment: Box<F::Env>,
+
+    /// Prefix (for example `MYAPP_`) used to bind every option without explicit environment variable
+    /// to an automatically derived one.
+    /// The variable name is made up of the prefix, the path of the group declaring the option and
+    /// the option name, for example `MYAPP_REMOTE_ADD_DRY_RUN` for the option `dry-run` of `remote add`.
+    pub env_prefix: Option<String>,
+
+    /// Configuration files to load option values from in ascending order of precedence
+    /// (for example system, user and project configuration), thus values of later files override
+    /// values of earlier ones.
+    /// Files that do not exist are skipped.
+    /// Values from configuration files take precedence over default values, but are overridden by
+    /// values specified via the command line or environment variables.
+    pub config_files: Vec<PathBuf>,
+
+    /// Whether to accept any unambiguous prefix of a sub-command or long option name (or alias),
+    /// for example `rem ad` for `remote add` or `--verb` for `--verbose`.
+    pub abbreviations: bool,
+
+    /// Whether to expand `@path` arguments to the arguments contained in the response file at that path.
+    /// Response files may reference further response files, while arguments starting with `@@` are
+    /// passed literally with the leading `@` removed.
+    pub response_files: bool,
+
+    /// Whether to continue parsing after an invalid option or argument to report all of them at
+    /// once (as `ParserError::Multiple` when there is more than one).
+    /// Errors in the group specifications and unresolvable sub-commands still abort parsing immediately.
+    pub collect_errors: bool,
+}
+
+/// Options to customize the parser of thread-safe groups (see `SyncGroup`), whose help printer and
+/// environment have to be `Send + Sync` as well.
+pub type SyncParseOptions<C = ()> = ParseOptions<C, SyncConsumer<C>>;
+
+impl<C, F: ?Sized + ConsumerKind<C>> Default for ParseOptions<C, F> {
+    fn default() -> Self {
+        ParseOptions {
+            help_printer: None,
+            option_syntax: OptionSyntax::Posix,
+            negative_numbers: NegativeNumberPolicy::PreferValue,
+            environment: F::process_environment(),
+            env_prefix: None,
+            config_files: Vec::new(),
+            response_files: false,
+            abbreviations: false,
+            collect_errors: false,
+        }
+    }
 }
 
 /// Parse from env::args() using the passed group.
-pub fn parse(group: Group, options: Option<ParseOptions>) -> Result<()> {
+pub fn parse<F>(group: Group<(), F>, options: Option<ParseOptions<(), F>>) -> result::Result<(), RunError>
+    where F: ?Sized + ConsumerKind<()> + Fn(&mut (), &arg::Values, &option::Values) -> CommandResult {
+    parse_with(group, &mut (), options)
+}
+
+/// Parse from env::args() using the passed group, handing the passed context to the consumer
+/// of the command context.
+pub fn parse_with<C, F>(group: Group<C, F>, context: &mut C, options: Option<ParseOptions<C, F>>) -> result::Result<(), RunError>
+    where F: ?Sized + ConsumerKind<C> + Fn(&mut C, &arg::Values, &option::Values) -> CommandResult {
     let args: Vec<String> = env::args().collect();
     let args: Vec<&str> = args.iter().map(AsRef::as_ref).collect();
 
-    parse_from(group, &args[..], options)
+    parse_from_with(group, context, &args[..], options)
+}
+
+/// Parse from env::args() using the passed group, printing errors to stderr and terminating the
+/// process with a conventional exit code (2 for usage errors, defined by the consumer otherwise).
+pub fn parse_or_exit<F>(group: Group<(), F>, options: Option<ParseOptions<(), F>>)
+    where F: ?Sized + ConsumerKind<()> + Fn(&mut (), &arg::Values, &option::Values) -> CommandResult {
+    parse_or_exit_with(group, &mut (), options)
+}
+
+/// Parse from env::args() using the passed group like `parse_or_exit`, handing the passed context
+/// to the consumer of the command context.
+pub fn parse_or_exit_with<C, F>(group: Group<C, F>, context: &mut C, options: Option<ParseOptions<C, F>>)
+    where F: ?Sized + ConsumerKind<C> + Fn(&mut C, &arg::Values, &option::Values) -> CommandResult {
+    if let Err(e) = parse_with(group, context, options) {
+        match &e {
+            RunError::Command(command_error) if command_error.error().is_none() => {} // Nothing to report
+            _ => eprintln!("{}", e),
+        }
+
+        process::exit(e.exit_code());
+    }
 }
 
 /// Parse the passed command line arguments using the passed group.
-pub fn parse_from(group: Group, args: &[&str], options: Option<ParseOptions>) -> Result<()> {
-    let group = Rc::new(group);
+/// Failures reported by the consumer of the command context are propagated as `RunError::Command`.
+pub fn parse_from<F>(group: Group<(), F>, args: &[&str], options: Option<ParseOptions<(), F>>) -> result::Result<(), RunError>
+    where F: ?Sized + ConsumerKind<()> + Fn(&mut (), &arg::Values, &option::Values) -> CommandResult {
+    parse_from_with(group, &mut (), args, options)
+}
+
+/// Parse the passed command line arguments using the passed group, handing the passed context
+/// (for example application state or a database handle) to the consumer of the command context.
+pub fn parse_from_with<C, F>(group: Group<C, F>, context: &mut C, args: &[&str], options: Option<ParseOptions<C, F>>) -> result::Result<(), RunError>
+    where F: ?Sized + ConsumerKind<C> + Fn(&mut C, &arg::Values, &option::Values) -> CommandResult {
+    Parser::new(group, options.unwrap_or_default()).parse_from_with(context, args)
+}
+
+/// Parse from env::args() using the passed group without invoking any consumer.
+pub fn get_matches<'g, C, F: ?Sized + ConsumerKind<C>>(group: Group<C, F>, options: Option<ParseOptions<C, F>>) -> Result<Matches<'g, C, F>> {
+    let args: Vec<String> = env::args().collect();
+    let args: Vec<&str> = args.iter().map(AsRef::as_ref).collect();
+
+    get_matches_from(group, &args[..], options)
+}
 
-    let (ctx_group, anticipated_options, parse_start_pos) = prepare_parsing_context(Rc::clone(&group), args)?;
-    let arg_descriptors = ctx_group.get_arguments();
+/// Parse the passed command line arguments using the passed group without invoking any consumer,
+/// leaving it to the caller to act on the returned matches (for example via `Matches::run`).
+pub fn get_matches_from<'g, C, F: ?Sized + ConsumerKind<C>>(group: Group<C, F>, args: &[&str], options: Option<ParseOptions<C, F>>) -> Result<Matches<'g, C, F>> {
+    Parser::new(group, options.unwrap_or_default()).get_matches_from(args)
+}
+
+/// Parser for a group (and its children) reusable to parse any number of command lines.
+/// The anticipated options of each command context and the lookups to find them by name are
+/// computed once when the command context is entered for the first time.
+/// The root group is either owned by the parser (see `Parser::new`) or borrowed for the lifetime
+/// `'g` (see `Parser::borrowed`).
+pub struct Parser<'g, C = (), F: ?Sized + ConsumerKind<C> = Consumer<C>> {
+    /// Root group of the command tree.
+    group: GroupRef<'g, C, F>,
+
+    /// Options customizing the parser.
+    options: ParseOptions<C, F>,
+
+    /// Command contexts computed so far by the path of their group.
+    contexts: Mutex<CommandContexts<'g, C, F>>,
+}
+
+impl<F> Parser<'_, (), F> where F: ?Sized + ConsumerKind<()> + Fn(&mut (), &arg::Values, &option::Values) -> CommandResult {
+    /// Parse the passed command line arguments.
+    /// Failures reported by the consumer of the command context are propagated as `RunError::Command`.
+    pub fn parse_from(&self, args: &[&str]) -> result::Result<(), RunError> {
+        self.parse_from_with(&mut (), args)
+    }
+}
 
-    let option_descriptor_lookup = prepare_option_descriptor_lookup(&anticipated_options)?;
+impl<'g, C, F: ?Sized + ConsumerKind<C>> Parser<'g, C, F> {
+    /// Create a parser for the passed group (or group shared via `Arc`) using the passed options.
+    pub fn new<G: Into<Arc<Group<C, F>>>>(group: G, options: ParseOptions<C, F>) -> Self {
+        Parser::with_group(GroupRef::Shared(group.into()), options)
+    }
 
-    let (raw_options, raw_arguments) = split_raw_arguments(&args[parse_start_pos..], &option_descriptor_lookup)?;
+    /// Create a parser for the passed borrowed group using the passed options, for example for a
+    /// group held in a struct of the caller.
+    pub fn borrowed(group: &'g Group<C, F>, options: ParseOptions<C, F>) -> Self {
+        Parser::with_group(GroupRef::Borrowed(group), options)
+    }
 
-    let mut option_value_lookup = parse_options(raw_options, &option_descriptor_lookup)?;
-    fill_default_options(&mut option_value_lookup, &anticipated_options);
+    /// Create a parser for the passed root group using the passed options.
+    fn with_group(group: GroupRef<'g, C, F>, options: ParseOptions<C, F>) -> Self {
+        Parser {
+            group,
+            options,
+            contexts: Mutex::new(HashMap::new()),
+        }
+    }
 
-    // Show help if specified as option
-    if let option::Value::Bool { value } = option_value_lookup.get(HELP_OPTION).unwrap() {
-        if *value {
-            show_help(
-                &ctx_group,
-                &anticipated_options,
-                arg_descriptors,
-                if options.is_some() { options.unwrap().help_printer } else { None },
-            );
+    /// Parse the passed command line arguments, handing the passed context to the consumer of
+    /// the command context.
+    pub fn parse_from_with(&self, context: &mut C, args: &[&str]) -> result::Result<(), RunError>
+        where F: Fn(&mut C, &arg::Values, &option::Values) -> CommandResult {
+        let matches = self.get_matches_from(args)?;
+        if matches.is_help_requested() {
+            matches.print_help(self.help_printer());
             return Ok(());
         }
+
+        // Call group consumer.
+        matches.run_with(context).map_err(RunError::Command)
     }
 
-    let argument_values = parse_arguments(arg_descriptors, raw_arguments)?;
+    /// Get the root group of the command tree.
+    pub fn group(&self) -> &Group<C, F> {
+        &self.group
+    }
 
-    // Call group consumer.
-    ctx_group.get_consumer()(&argument_values, &option_value_lookup);
-    Ok(())
+    /// Get the help printer to use (if not the default one).
+    pub(crate) fn help_printer(&self) -> Option<&dyn HelpPrinter<C, F>> {
+        self.options.help_printer.as_deref().map(F::as_help_printer)
+    }
+
+    /// Parse the passed command line arguments without invoking any consumer.
+    pub fn get_matches_from(&self, args: &[&str]) -> Result<Matches<'g, C, F>> {
+        let options = &self.options;
+
+        let expanded_args = if options.response_files { Some(response_file::expand(args)?) } else { None };
+        let expanded_arg_refs: Vec<&str>;
+        let args = match &expanded_args {
+            Some(expanded_args) => {
+                expanded_arg_refs = expanded_args.iter().map(AsRef::as_ref).collect();
+                &expanded_arg_refs[..]
+            }
+            None => args,
+        };
+
+        let ParsingContext {
+            context,
+            path: group_path,
+            args: ctx_args,
+            indices: ctx_indices,
+        } = self.prepare_parsing_context(args)?;
+        let ctx_group = &context.group;
+        let anticipated_options = &context.options;
+        let arg_descriptors = ctx_group.get_arguments();
+        let in_context = |e: ParserError| e.in_group(&group_path);
+
+        let mut diagnostics = Diagnostics::new(options.collect_errors);
+
+        let raw = split_raw_arguments(&ctx_args, &ctx_indices, &context.lookup, arg_descriptors, options, &mut diagnostics).map_err(in_context)?;
+
+        let mut option_value_lookup = parse_options(raw.options, &mut diagnostics).map_err(in_context)?;
+        fill_environment_options(&mut option_value_lookup, anticipated_options, F::as_environment(options.environment.as_ref()), &mut diagnostics).map_err(in_context)?;
+        fill_config_options(&mut option_value_lookup, anticipated_options, &group_path, &options.config_files, &mut diagnostics).map_err(in_context)?;
+        fill_default_options(&mut option_value_lookup, anticipated_options);
+
+        // Skip checking arguments if help is specified as option (and the options are valid)
+        if let Some(option::Value::Bool { value }) = option_value_lookup.get(HELP_OPTION) {
+            if *value && diagnostics.is_empty() {
+                let argument_values = arg::Values::new(Vec::new(), HashMap::new(), Vec::new());
+                return Ok(Matches::new(context, group_path, argument_values, option_value_lookup, true));
+            }
+        }
+
+        if let Err(e) = check_required_options(&option_value_lookup, anticipated_options) {
+            diagnostics.report(e).map_err(in_context)?;
+        }
+
+        // Argument errors are collected separately, as they are superseded by a mistyped sub-command
+        let mut argument_diagnostics = Diagnostics::new(true);
+        let argument_values = match parse_arguments(arg_descriptors, &raw.arguments, &mut argument_diagnostics) {
+            Ok(argument_values) if argument_diagnostics.is_empty() => argument_values,
+            result => {
+                // Arguments not matching the expected ones may be a mistyped sub-command
+                let positional = &raw.arguments[..raw.arguments.len() - raw.trailing.len()];
+                match unknown_child_error(ctx_group, positional) {
+                    Some(e) => diagnostics.report(e).map_err(in_context)?,
+                    None => {
+                        for e in argument_diagnostics.errors.into_iter().chain(result.err()) {
+                            diagnostics.report(e).map_err(in_context)?;
+                        }
+                    }
+                }
+                Vec::new()
+            }
+        };
+        diagnostics.finish(&group_path)?;
+
+        let argument_positions = arg_descriptors.iter()
+            .enumerate()
+            .filter_map(|(i, d)| d.name().map(|name| (name.clone(), i)))
+            .collect();
+        let argument_values = arg::Values::new(argument_values, argument_positions, raw.trailing.iter().map(|s| String::from(*s)).collect());
+
+        Ok(Matches::new(context, group_path, argument_values, option_value_lookup, false))
+    }
+
+    /// Get the command context of the group at the passed path, computing it on first use.
+    /// The path has to consist of canonical child names known to exist.
+    fn command_context(&self, path: &[String]) -> Result<Arc<CommandContext<'g, C, F>>> {
+        if let Some(context) = self.contexts.lock().unwrap().get(path) {
+            return Ok(Arc::clone(context));
+        }
+
+        let (group, mut anticipated_options) = match path.split_last() {
+            Some((name, parent_path)) => {
+                let parent = self.command_context(parent_path)?;
+                let mut anticipated_options = parent.options.clone();
+
+                // Local options of the parent group are not inherited
+                anticipated_options.retain(|_, descriptor| !descriptor.is_local());
+
+                (GroupRef::Shared(parent.group.get_child_known_for(name).unwrap()), anticipated_options)
+            }
+            None => {
+                let mut anticipated_options: OptionDescriptors = HashMap::new();
+
+                // Add help option to anticipated options.
+                let help_option_descriptor = option::Descriptor::new(HELP_OPTION, option::Type::Bool { default: false }, "Get this information displayed")
+                    .with_short_name(HELP_OPTION_SHORT_NAME);
+                anticipated_options.insert(help_option_descriptor.take_name(), Arc::new(help_option_descriptor));
+
+                (self.group.clone(), anticipated_options)
+            }
+        };
+
+        // Save current groups options.
+        add_group_options(&mut anticipated_options, &group, path, &self.options).map_err(|e| e.in_group(path))?;
+        let lookup = prepare_option_descriptor_lookup(&anticipated_options, &self.options).map_err(|e| e.in_group(path))?;
+
+        let context = Arc::new(CommandContext {
+            group,
+            options: anticipated_options,
+            lookup,
+        });
+
+        // Another thread may have computed the command context in the meantime
+        let mut contexts = self.contexts.lock().unwrap();
+        Ok(Arc::clone(contexts.entry(path.to_vec()).or_insert(context)))
+    }
+
+    /// Prepare the parsing context for the passed arguments.
+    /// Options (and their values) of groups already entered may precede or be placed between the
+    /// group names, so that global options may be specified anywhere on the command line.
+    /// Returns the command context as well as the rest of the raw command line arguments to parse.
+    fn prepare_parsing_context<'a>(&self, args: &[&'a str]) -> Result<ParsingContext<'a, 'g, C, F>> {
+        let options = &self.options;
+        let mut group_path: Vec<String> = Vec::new();
+        let mut ctx_args: Vec<&'a str> = Vec::with_capacity(args.len());
+        let mut ctx_indices: Vec<usize> = Vec::with_capacity(args.len());
+
+        // Find command context (via specified groups).
+        let mut context = self.command_context(&group_path)?;
+        let mut args_pos = 1;
+
+        while args_pos < args.len() {
+            let arg = args[args_pos];
+            if arg == END_OF_OPTIONS {
+                break; // Everything following the terminator is a positional argument
+            }
+
+            if !is_value(arg, false, &context.lookup, options) {
+                // Skip option (and its value) of a group already entered
+                let mut next_pos = args_pos + 1;
+                if split_option(arg, args_pos, args, &mut next_pos, &context.lookup, options, &mut Vec::new()).is_err() {
+                    next_pos = args_pos + 1; // Unknown option (possibly of a group not yet entered)
+                }
+
+                ctx_args.extend_from_slice(&args[args_pos..next_pos]);
+                ctx_indices.extend(args_pos..next_pos);
+                args_pos = next_pos;
+                continue;
+            }
+
+            match get_child_name_for(&context.group, arg, options).map_err(|e| e.at_index(args_pos).in_group(&group_path))? {
+                Some(name) => {
+                    group_path.push(name.to_string());
+                    context = self.command_context(&group_path)?;
+                }
+                None => break // Command context path found
+            };
+
+            args_pos += 1;
+        }
+        ctx_args.extend_from_slice(&args[args_pos..]);
+        ctx_indices.extend(args_pos..args.len());
+
+        Ok(ParsingContext {
+            context,
+            path: group_path,
+            args: ctx_args,
+            indices: ctx_indices,
+        })
+    }
+}
+
+/// Command contexts by the path of their group.
+type CommandContexts<'g, C, F> = HashMap<Vec<String>, Arc<CommandContext<'g, C, F>>>;
+
+/// Command context provided by a group: the group along with the options anticipated in it.
+pub(crate) struct CommandContext<'g, C, F: ?Sized> {
+    /// Group providing the command context.
+    pub(crate) group: GroupRef<'g, C, F>,
+
+    /// Anticipated options of all groups on the path from the root group to the context group.
+    pub(crate) options: OptionDescriptors,
+
+    /// Lookup of the anticipated options by the names they are known for on the command line.
+    lookup: OptionLookup,
+}
+
+/// Group either borrowed from the caller of the parser or shared via `Arc`.
+pub(crate) enum GroupRef<'g, C, F: ?Sized> {
+    Borrowed(&'g Group<C, F>),
+    Shared(Arc<Group<C, F>>),
+}
+
+impl<C, F: ?Sized> Clone for GroupRef<'_, C, F> {
+    fn clone(&self) -> Self {
+        match self {
+            GroupRef::Borrowed(group) => GroupRef::Borrowed(group),
+            GroupRef::Shared(group) => GroupRef::Shared(Arc::clone(group)),
+        }
+    }
+}
+
+impl<C, F: ?Sized> Deref for GroupRef<'_, C, F> {
+    type Target = Group<C, F>;
+
+    fn deref(&self) -> &Group<C, F> {
+        match self {
+            GroupRef::Borrowed(group) => group,
+            GroupRef::Shared(group) => group,
+        }
+    }
 }
 
-/// Prepare the parsing context for the passed group and arguments.
-/// Returns the group context, anticipated options to parse as well as the rest of the raw
-/// command line arguments to parse.
-fn prepare_parsing_context<'a>(group: Rc<Group>, args: &[&str]) -> Result<(Rc<Group>, HashMap<Rc<String>, Rc<option::Descriptor>>, usize)> {
-    let mut anticipated_options: HashMap<Rc<String>, Rc<option::Descriptor>> = HashMap::new();
+/// Collector of the errors encountered while parsing.
+struct Diagnostics {
+    /// Whether to continue parsing after an error.
+    collect: bool,
 
-    // Add help option to anticipated options.
-    let help_option_descriptor = option::Descriptor::new(HELP_OPTION, option::Type::Bool { default: false }, "Get this information displayed")
-        .add_alias(HELP_OPTION_ALIAS);
-    anticipated_options.insert(help_option_descriptor.take_name(), Rc::new(help_option_descriptor));
+    /// Errors collected so far.
+    errors: Vec<ParserError>,
+}
+
+impl Diagnostics {
+    fn new(collect: bool) -> Self {
+        Diagnostics {
+            collect,
+            errors: Vec::new(),
+        }
+    }
+
+    /// Report the passed error.
+    /// Returns the error to abort parsing unless errors are collected.
+    fn report(&mut self, error: ParserError) -> Result<()> {
+        if !self.collect {
+            return Err(error);
+        }
+
+        self.errors.push(error);
+        Ok(())
+    }
+
+    /// Check whether no error has been collected.
+    fn is_empty(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    /// Finish parsing, failing with the collected errors (ordered by index, errors without index last)
+    /// occurred in the context of the group with the passed path.
+    fn finish(self, group_path: &[String]) -> Result<()> {
+        let mut errors: Vec<ParserError> = self.errors.into_iter().map(|e| e.in_group(group_path)).collect();
+        errors.sort_by_key(|e| e.index().unwrap_or(usize::MAX)); // Stable, thus keeping the order of errors without index
+
+        match errors.len() {
+            0 => Ok(()),
+            1 => Err(errors.remove(0)),
+            _ => Err(ParserError::Multiple { errors }),
+        }
+    }
+}
+
+/// Context of the command to parse.
+struct ParsingContext<'a, 'g, C, F: ?Sized> {
+    /// Command context of the group addressed by the command line.
+    context: Arc<CommandContext<'g, C, F>>,
+
+    /// Names of the groups on the path from the root group to the context group.
+    path: Vec<String>,
+
+    /// Raw command line arguments to parse in the command context (without program and group names).
+    args: Vec<&'a str>,
+
+    /// Indices of the raw command line arguments to parse in all passed arguments.
+    indices: Vec<usize>,
+}
+
+/// Get the name of the child of the passed group known for the passed raw argument.
+/// When abbreviations are enabled, the argument may be an unambiguous prefix of a child name or alias.
+fn get_child_name_for<C, F: ?Sized + ConsumerKind<C>>(group: &Group<C, F>, arg: &str, options: &ParseOptions<C, F>) -> Result<Option<Arc<String>>> {
+    if let Some(name) = group.get_child_name_known_for(arg) {
+        return Ok(Some(Arc::clone(name)));
+    }
+    if !options.abbreviations || arg.is_empty() {
+        return Ok(None);
+    }
+
+    let candidates = group.get_child_names_known_for_prefix(arg);
+    match candidates.len() {
+        0 => Ok(None),
+        1 => Ok(Some(Arc::clone(candidates[0]))),
+        _ => Err(ParserError::AmbiguousSubCommand {
+            token: String::from(arg),
+            index: None,
+            candidates: candidates.iter().map(|c| c.to_string()).collect(),
+            group_path: Vec::new(),
+        }),
+    }
+}
+
+/// Add the options of the passed group to the anticipated options.
+/// Options hidden by the group are removed, while options overriding inherited ones replace them.
+fn add_group_options<C, F: ?Sized + ConsumerKind<C>>(anticipated_options: &mut OptionDescriptors, group: &Group<C, F>, group_path: &[String], options: &ParseOptions<C, F>) -> Result<()> {
+    for option_name in group.get_hidden_options() {
+        if anticipated_options.remove(option_name).is_none() {
+            return Err(ParserError::DefinitionConflict {
+                name: option_name.clone(),
+                message: format!("Option '{}' hides no inherited option", option_name),
+                group_path: Vec::new(),
+            });
+        }
+    }
 
-    // Save root groups options.
     for (option_name, option_descriptor) in group.get_options() {
-        anticipated_options.insert(Rc::clone(option_name), Rc::clone(option_descriptor));
+        let overrides = group.overrides_option(option_name);
+
+        if anticipated_options.contains_key(option_name) && !overrides {
+            return Err(ParserError::DefinitionConflict {
+                name: option_name.to_string(),
+                message: format!("Option '{}' declared multiple times in group specifications", option_name),
+                group_path: Vec::new(),
+            });
+        } else if !anticipated_options.contains_key(option_name) && overrides {
+            return Err(ParserError::DefinitionConflict {
+                name: option_name.to_string(),
+                message: format!("Option '{}' overrides no inherited option", option_name),
+                group_path: Vec::new(),
+            });
+        }
+
+        anticipated_options.insert(Arc::clone(option_name), bind_to_env(option_descriptor, group_path, options));
+    }
+
+    Ok(())
+}
+
+/// Bind the passed option descriptor to an environment variable derived from the configured
+/// environment variable prefix and the path of the declaring group, unless already bound.
+fn bind_to_env<C, F: ?Sized + ConsumerKind<C>>(descriptor: &Arc<option::Descriptor>, group_path: &[String], options: &ParseOptions<C, F>) -> Arc<option::Descriptor> {
+    match &options.env_prefix {
+        Some(prefix) if descriptor.env().is_none() => {
+            let mut variable = prefix.clone();
+            for segment in group_path.iter().chain(std::iter::once(descriptor.name())) {
+                if !variable.is_empty() && !variable.ends_with('_') {
+                    variable.push('_');
+                }
+                variable.extend(segment.chars().map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_uppercase() } else { '_' }));
+            }
+
+            Arc::new(descriptor.as_ref().clone().with_env(&variable))
+        }
+        _ => Arc::clone(descriptor),
     }
+}
 
-    // Find command context (via specified groups).
-    let mut cur_group = group;
-    let mut args_pos = 1;
+/// Option descriptor known for a long name or alias.
+struct LongName {
+    /// Descriptor of the option.
+    descriptor: Arc<option::Descriptor>,
 
-    for arg in &args[1..] {
-        let arg = *arg;
+    /// Whether the name is the negated form of a negatable flag (for example `no-color`).
+    negated: bool,
+}
+
+/// Lookup of option descriptors by the names they are known for on the command line.
+pub(crate) struct OptionLookup {
+    /// Option descriptors by long name or alias (including negated forms).
+    long: HashMap<String, LongName>,
 
-        match cur_group.get_child_known_for(arg) {
-            Some(v) => {
-                cur_group = v;
+    /// Option descriptors by short name.
+    short: HashMap<char, Arc<option::Descriptor>>,
 
-                // Save current groups options.
-                for (option_name, option_descriptor) in cur_group.get_options() {
-                    if anticipated_options.contains_key(option_name) {
-                        return Err(ParserError {
-                            message: format!("Option '{}' declared multiple times in group specifications", option_name)
+    /// Whether to accept unambiguous prefixes of long names and aliases.
+    abbreviations: bool,
+}
+
+/// Prepare a lookup to find option descriptors by their name, alias (including negated forms of
+/// negatable flags) or short name.
+fn prepare_option_descriptor_lookup<C, F: ?Sized + ConsumerKind<C>>(anticipated_options: &OptionDescriptors, options: &ParseOptions<C, F>) -> Result<OptionLookup> {
+    let syntax = options.option_syntax;
+    let mut lookup = OptionLookup {
+        long: HashMap::new(),
+        short: HashMap::new(),
+        abbreviations: options.abbreviations,
+    };
+
+    for option_descriptor in anticipated_options.values() {
+        let mut long_names: Vec<String> = Vec::with_capacity(option_descriptor.get_aliases().len() + 2);
+        long_names.push(option_descriptor.name().clone());
+        long_names.extend(option_descriptor.get_aliases().iter().cloned());
+
+        if let Some(short_name) = option_descriptor.short_name() {
+            match syntax {
+                OptionSyntax::Posix => {
+                    if lookup.short.contains_key(&short_name) {
+                        return Err(ParserError::DefinitionConflict {
+                            name: short_name.to_string(),
+                            message: format!("Short option name '{}' specified more than once", short_name),
+                            group_path: Vec::new(),
                         });
                     }
-                    anticipated_options.insert(Rc::clone(option_name), Rc::clone(option_descriptor));
+                    lookup.short.insert(short_name, Arc::clone(option_descriptor));
                 }
+                OptionSyntax::AnyDashes => long_names.push(short_name.to_string()),
             }
-            None => break // Command context path found
+        }
+
+        let negated_names: Vec<String> = if option_descriptor.is_negatable() {
+            long_names.iter().map(|name| format!("{}{}", NEGATION_PREFIX, name)).collect()
+        } else {
+            Vec::new()
         };
 
-        args_pos += 1;
+        let names = long_names.into_iter().map(|name| (name, false))
+            .chain(negated_names.into_iter().map(|name| (name, true)));
+        for (long_name, negated) in names {
+            if lookup.long.contains_key(&long_name) {
+                return Err(ParserError::DefinitionConflict {
+                    message: format!("Option name or alias '{}' specified more than once", long_name),
+                    name: long_name,
+                    group_path: Vec::new(),
+                });
+            }
+            lookup.long.insert(long_name, LongName {
+                descriptor: Arc::clone(option_descriptor),
+                negated,
+            });
+        }
     }
 
-    Ok((cur_group, anticipated_options, args_pos))
+    Ok(lookup)
 }
 
-/// Prepare a lookup to find option descriptors by their name or alias.
-fn prepare_option_descriptor_lookup(anticipated_options: &HashMap<Rc<String>, Rc<option::Descriptor>>) -> Result<HashMap<&String, &option::Descriptor>> {
-    let mut option_descriptor_lookup = HashMap::new();
+/// Get the option descriptor for the passed option name or alias.
+/// When abbreviations are enabled, the name may be an unambiguous prefix of a name or alias.
+fn get_option_descriptor_for_name<'a>(option_name: &str, option_descriptor_lookup: &'a OptionLookup) -> Result<&'a LongName> {
+    if let Some(o) = option_descriptor_lookup.long.get(option_name) {
+        return Ok(o);
+    }
 
-    for (option_name, option_descriptor) in anticipated_options {
-        if option_descriptor_lookup.contains_key(option_name.as_ref()) {
-            return Err(ParserError {
-                message: format!("Option name or alias '{}' specified more than once", option_name.as_ref()),
-            });
-        }
-        option_descriptor_lookup.insert(option_name.as_ref(), option_descriptor.as_ref());
+    let mut candidates: Vec<(String, &'a LongName)> = Vec::new();
+    if option_descriptor_lookup.abbreviations && !option_name.is_empty() {
+        for (long_name, o) in &option_descriptor_lookup.long {
+            let is_known_candidate = candidates.iter()
+                .any(|(_, c)| c.descriptor.name() == o.descriptor.name() && c.negated == o.negated);
 
-        for alias in option_descriptor.get_aliases() {
-            if option_descriptor_lookup.contains_key(alias) {
-                return Err(ParserError {
-                    message: format!("Option name or alias '{}' specified more than once", alias),
-                });
+            if long_name.starts_with(option_name) && !is_known_candidate {
+                let name = if o.negated { format!("{}{}", NEGATION_PREFIX, o.descriptor.name()) } else { o.descriptor.name().clone() };
+                candidates.push((name, o));
             }
-            option_descriptor_lookup.insert(alias, option_descriptor.as_ref());
         }
     }
 
-    Ok(option_descriptor_lookup)
+    match candidates.len() {
+        0 => {
+            let suggestions = suggestion::similar_names(option_name, option_descriptor_lookup.long.keys().map(String::as_str))
+                .into_iter()
+                .map(|name| format!("{}{}", LONG_OPTION_PREFIX, name))
+                .collect();
+
+            Err(ParserError::UnknownOption {
+                token: format!("{}{}", LONG_OPTION_PREFIX, option_name),
+                index: None,
+                suggestions,
+                group_path: Vec::new(),
+            })
+        }
+        1 => Ok(candidates[0].1),
+        _ => {
+            let mut names: Vec<String> = candidates.iter().map(|(name, _)| format!("{}{}", LONG_OPTION_PREFIX, name)).collect();
+            names.sort();
+
+            Err(ParserError::AmbiguousOption {
+                token: format!("{}{}", LONG_OPTION_PREFIX, option_name),
+                index: None,
+                candidates: names,
+                group_path: Vec::new(),
+            })
+        }
+    }
 }
 
-/// Get the option descriptor for the passed option name.
-fn get_option_descriptor_for_name<'a>(option_name: &str, option_descriptor_lookup: &HashMap<&String, &'a option::Descriptor>) -> Result<&'a option::Descriptor> {
-    match option_descriptor_lookup.get(&String::from(option_name)) {
-        Some(o) => Ok(*o),
-        None => Err(ParserError {
-            message: format!("Option '--{}' is unknown in the command context", option_name)
-        })
+/// Get the option descriptor for the passed short option name.
+/// When unknown, long options resembling the whole cluster the short name is part of are suggested
+/// (for example `--verbose` for a mistyped `-verbose`).
+fn get_option_descriptor_for_short_name<'a>(short_name: char, cluster: &str, option_descriptor_lookup: &'a OptionLookup) -> Result<&'a option::Descriptor> {
+    match option_descriptor_lookup.short.get(&short_name) {
+        Some(o) => Ok(o),
+        None => {
+            let suggestions = if cluster.chars().count() > 1 {
+                suggestion::similar_names(cluster, option_descriptor_lookup.long.keys().map(String::as_str))
+                    .into_iter()
+                    .map(|name| format!("{}{}", LONG_OPTION_PREFIX, name))
+                    .collect()
+            } else {
+                Vec::new()
+            };
+
+            Err(ParserError::UnknownOption {
+                token: format!("{}{}", OPTION_PREFIX, short_name),
+                index: None,
+                suggestions,
+                group_path: Vec::new(),
+            })
+        }
     }
 }
 
 /// Check whether the passed raw argument string is a option.
+/// A single `-` is not an option but an argument (commonly referring to stdin).
 fn is_option(raw_arg: &str) -> bool {
-    raw_arg.starts_with(OPTION_PREFIX)
+    raw_arg.starts_with(OPTION_PREFIX) && raw_arg.len() > 1
+}
+
+/// Check whether the passed raw argument string is a negative number literal (for example `-5` or `-3.2`).
+fn is_negative_number(raw_arg: &str) -> bool {
+    match raw_arg.strip_prefix(OPTION_PREFIX) {
+        Some(number) => number.starts_with(|c: char| c.is_ascii_digit() || c == '.') && number.parse::<f64>().is_ok(),
+        None => false,
+    }
+}
+
+/// Check whether an option is known for the passed raw argument string.
+fn is_known_option(raw_arg: &str, option_descriptor_lookup: &OptionLookup, syntax: OptionSyntax) -> bool {
+    if syntax == OptionSyntax::AnyDashes || raw_arg.starts_with(LONG_OPTION_PREFIX) {
+        let raw_option = raw_arg.trim_start_matches(OPTION_PREFIX);
+        let option_name = raw_option.split(OPTION_KEY_VALUE_SPLIT).next().unwrap_or(raw_option);
+
+        get_option_descriptor_for_name(option_name, option_descriptor_lookup).is_ok()
+    } else {
+        match raw_arg[1..].chars().next() {
+            Some(short_name) => option_descriptor_lookup.short.contains_key(&short_name),
+            None => false,
+        }
+    }
+}
+
+/// Check whether the passed raw argument string is a value rather than an option.
+/// Arguments looking like negative numbers are values when no option of that name exists or
+/// when a numeric value is expected and the negative number policy prefers values.
+fn is_value<C, F: ?Sized + ConsumerKind<C>>(raw_arg: &str, numeric_expected: bool, option_descriptor_lookup: &OptionLookup, options: &ParseOptions<C, F>) -> bool {
+    if !is_option(raw_arg) {
+        return true;
+    }
+
+    if options.negative_numbers == NegativeNumberPolicy::Disabled || !is_negative_number(raw_arg) {
+        return false;
+    }
+
+    if !is_known_option(raw_arg, option_descriptor_lookup, options.option_syntax) {
+        return true;
+    }
+
+    numeric_expected && options.negative_numbers == NegativeNumberPolicy::PreferValue
+}
+
+/// Option occurrence on the command line along with its raw value.
+struct RawOption<'a, 'd> {
+    /// Descriptor of the option.
+    descriptor: &'d option::Descriptor,
+
+    /// Option as it has been written on the command line (for example `--name` or `-n`).
+    spelling: String,
+
+    /// Raw value of the option.
+    value: &'a str,
+
+    /// Index of the option in the raw command line arguments.
+    index: usize,
+}
+
+/// Positional argument on the command line.
+struct RawArgument<'a> {
+    /// Raw value of the argument.
+    value: &'a str,
+
+    /// Index of the argument in the raw command line arguments.
+    index: usize,
+}
+
+/// Raw command line arguments split into options and arguments.
+struct RawArguments<'a, 'd> {
+    /// Raw options in the order they have been specified.
+    options: Vec<RawOption<'a, 'd>>,
+
+    /// Raw positional arguments (including the ones following the end-of-options terminator).
+    arguments: Vec<RawArgument<'a>>,
+
+    /// Raw arguments following the end-of-options terminator `--`.
+    trailing: Vec<&'a str>,
 }
 
 /// Split the passed raw command line arguments into options (name and value) and arguments.
-fn split_raw_arguments<'a>(args: &[&'a str], option_descriptor_lookup: &HashMap<&String, &option::Descriptor>) -> Result<(HashMap<&'a str, &'a str>, Vec<&'a str>)> {
-    let mut raw_options = HashMap::new();
+/// Every argument following the end-of-options terminator `--` is treated as positional argument.
+fn split_raw_arguments<'a, 'd, C, F: ?Sized + ConsumerKind<C>>(
+    args: &[&'a str],
+    indices: &[usize],
+    option_descriptor_lookup: &'d OptionLookup,
+    arg_descriptors: &[arg::Descriptor],
+    options: &ParseOptions<C, F>,
+    diagnostics: &mut Diagnostics,
+) -> Result<RawArguments<'a, 'd>> {
+    let mut raw_options = Vec::new();
     let mut raw_arguments = Vec::new();
+    let mut trailing = Vec::new();
+
+    let mut i = 0;
+    while i < args.len() {
+        let arg = args[i];
+        i += 1;
 
-    let mut skip_next = false;
-    for i in 0..args.len() {
-        if skip_next {
-            skip_next = false;
+        if arg == END_OF_OPTIONS {
+            trailing.extend_from_slice(&args[i..]);
+            raw_arguments.extend(args[i..].iter().zip(&indices[i..]).map(|(value, index)| RawArgument { value, index: *index }));
+            break;
+        }
+
+        let numeric_argument_expected = match get_argument_descriptor_at(arg_descriptors, raw_arguments.len()) {
+            Some(descriptor) => descriptor.value_type().is_numeric(),
+            None => false,
+        };
+        let index = indices[i - 1];
+        if is_value(arg, numeric_argument_expected, option_descriptor_lookup, options) {
+            raw_arguments.push(RawArgument { value: arg, index });
             continue;
         }
 
-        let arg = args[i];
+        if let Err(e) = split_option(arg, index, args, &mut i, option_descriptor_lookup, options, &mut raw_options) {
+            diagnostics.report(e.at_index(index))?;
+        }
+    }
 
-        if is_option(arg) {
-            let raw_option = arg.trim_start_matches(OPTION_PREFIX); // Strip leading '-' chars
+    Ok(RawArguments {
+        options: raw_options,
+        arguments: raw_arguments,
+        trailing,
+    })
+}
 
-            let is_key_value_option = arg.contains(OPTION_KEY_VALUE_SPLIT);
-            let (option_name, option_value) = if is_key_value_option {
-                // Value is in same string separated by '='
-                let parts: Vec<&str> = raw_option.split(OPTION_KEY_VALUE_SPLIT).collect();
-                (parts[0], parts[1])
-            } else {
-                // Value is in next raw command line argument
-                let next_arg = if args.len() > i + 1 { Some(&args[i + 1]) } else { None };
+/// Split the passed raw option argument (for example `--name=value` or `-vxf`) into options and their
+/// values. The following raw command line argument at the passed position may be consumed as value.
+/// The passed index is the index of the option argument in the raw command line arguments.
+fn split_option<'a, 'd, C, F: ?Sized + ConsumerKind<C>>(
+    arg: &'a str,
+    index: usize,
+    args: &[&'a str],
+    pos: &mut usize,
+    option_descriptor_lookup: &'d OptionLookup,
+    options: &ParseOptions<C, F>,
+    raw_options: &mut Vec<RawOption<'a, 'd>>,
+) -> Result<()> {
+    if options.option_syntax == OptionSyntax::AnyDashes || arg.starts_with(LONG_OPTION_PREFIX) {
+        let raw_option = arg.trim_start_matches(OPTION_PREFIX); // Strip leading '-' chars
+
+        let (option_name, inline_value) = match raw_option.split_once(OPTION_KEY_VALUE_SPLIT) {
+            Some((name, value)) => (name, Some(value)), // Value is in same string separated by '='
+            None => (raw_option, None),
+        };
+        let long_name = get_option_descriptor_for_name(option_name, option_descriptor_lookup)?;
+        let (descriptor, negated): (&'d option::Descriptor, bool) = (&long_name.descriptor, long_name.negated);
+        let spelling = String::from(&arg[..arg.len() - inline_value.map_or(0, |v| v.len() + 1)]); // As typed, without value
 
-                let is_option_without_value = next_arg.is_none()
-                    || is_option(next_arg.unwrap());
+        let value = match (inline_value, negated) {
+            (Some(v), true) => return Err(unexpected_value_error(spelling, v)),
+            (Some(v), false) if is_count(descriptor) => return Err(unexpected_value_error(spelling, v)),
+            (None, true) => NEGATED_FLAG_VALUE,
+            (Some(v), false) => v,
+            (None, false) => take_option_value(descriptor, &spelling, args, pos, option_descriptor_lookup, options)?,
+        };
 
-                let option_value = if is_option_without_value {
-                    // Option without value! Only allowed for boolean options.
-                    let option_type = get_option_descriptor_for_name(raw_option, option_descriptor_lookup)?.value_type();
+        raw_options.push(RawOption { descriptor, spelling, value, index });
+    } else {
+        // Cluster of short options (for example `-vxf file`)
+        let cluster = &arg[1..];
 
-                    match option_type {
-                        option::Type::Bool { default: _ } => "true",
-                        _ => return Err(ParserError {
-                            message: format!("Encountered option '{}' without value that is not of type boolean. Specify a value for the option.", raw_option)
-                        })
-                    }
+        for (char_pos, short_name) in cluster.char_indices() {
+            let descriptor = get_option_descriptor_for_short_name(short_name, cluster, option_descriptor_lookup)?;
+            let spelling = format!("{}{}", OPTION_PREFIX, short_name);
+            let rest = &cluster[char_pos + short_name.len_utf8()..];
+
+            if let Some(value) = rest.strip_prefix(OPTION_KEY_VALUE_SPLIT) {
+                // Value attached via '=' (for example `-o=file`)
+                if is_count(descriptor) {
+                    return Err(unexpected_value_error(spelling, value));
+                }
+
+                raw_options.push(RawOption { descriptor, spelling, value, index });
+                break;
+            }
+
+            if descriptor.value_type().is_flag() {
+                // Flags within a cluster of several short options never consume the next argument
+                let value = if cluster.len() == short_name.len_utf8() {
+                    take_option_value(descriptor, &spelling, args, pos, option_descriptor_lookup, options)?
                 } else {
-                    next_arg.unwrap()
+                    flag_value(descriptor.value_type())
                 };
 
-                skip_next = true; // Skip the next raw command line argument since it was already processed
+                raw_options.push(RawOption { descriptor, spelling, value, index });
+                continue;
+            }
 
-                (raw_option, option_value)
+            // Value is either attached (for example `-ofile`) or the next raw command line argument
+            let value = if rest.is_empty() {
+                take_option_value(descriptor, &spelling, args, pos, option_descriptor_lookup, options)?
+            } else {
+                rest
             };
 
-            raw_options.insert(option_name, option_value);
-        } else {
-            raw_arguments.push(arg);
+            raw_options.push(RawOption { descriptor, spelling, value, index });
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+/// Check whether the passed option is a counted flag, whose value is the number of its occurrences.
+fn is_count(descriptor: &option::Descriptor) -> bool {
+    matches!(descriptor.value_type(), option::Type::Count { default: _ })
+}
+
+/// Create the error for a value passed to an option not taking any (for example `--no-color=false`).
+fn unexpected_value_error(spelling: String, value: &str) -> ParserError {
+    ParserError::UnexpectedValue {
+        option: spelling,
+        value: String::from(value),
+        index: None,
+        group_path: Vec::new(),
+    }
+}
+
+/// Take the value of the passed option from the raw command line argument at the passed position.
+/// Advances the position when the argument has been consumed as value.
+fn take_option_value<'a, C, F: ?Sized + ConsumerKind<C>>(
+    descriptor: &option::Descriptor,
+    spelling: &str,
+    args: &[&'a str],
+    pos: &mut usize,
+    option_descriptor_lookup: &OptionLookup,
+    options: &ParseOptions<C, F>,
+) -> Result<&'a str> {
+    let next_arg = args.get(*pos).copied();
+
+    match descriptor.value_type() {
+        option::Type::Bool { default: _ } => {
+            // Boolean options only consume the next argument when it is an explicit boolean value.
+            return Ok(match next_arg {
+                Some(v) if v.parse::<bool>().is_ok() => {
+                    *pos += 1;
+                    v
+                }
+                _ => FLAG_VALUE,
+            });
         }
+        option::Type::Count { default: _ } => return Ok(COUNT_FLAG_VALUE), // Counted flags never consume a value
+        _ => {}
     }
 
-    Ok((raw_options, raw_arguments))
+    match next_arg {
+        Some(v) if is_value(v, descriptor.value_type().is_numeric(), option_descriptor_lookup, options) => {
+            *pos += 1;
+            Ok(v)
+        }
+        _ => Err(ParserError::MissingValue {
+            option: String::from(spelling),
+            index: None,
+            expected: descriptor.value_type().clone(),
+            group_path: Vec::new(),
+        })
+    }
+}
+
+/// Get the implicit value of the passed flag type when specified on the command line.
+fn flag_value(option_type: &option::Type) -> &'static str {
+    match option_type {
+        option::Type::Count { default: _ } => COUNT_FLAG_VALUE,
+        _ => FLAG_VALUE,
+    }
 }
 
 /// Parse raw options to their actual values.
-fn parse_options<'a>(raw_options: HashMap<&str, &str>, option_descriptor_lookup: &HashMap<&String, &'a option::Descriptor>) -> Result<HashMap<&'a str, option::Value>> {
-    let mut option_value_lookup: HashMap<&str, option::Value> = HashMap::new();
+/// Values of list options are collected and occurrences of counted flags summed up.
+fn parse_options(raw_options: Vec<RawOption>, diagnostics: &mut Diagnostics) -> Result<option::Values> {
+    let mut option_value_lookup = option::Values::default();
+
+    for raw_option in raw_options {
+        let option_name: &str = raw_option.descriptor.name();
+        let is_count = is_count(raw_option.descriptor);
+        let option_value = match parse_option(&raw_option) {
+            Ok(v) => v,
+            Err(e) => {
+                diagnostics.report(e)?;
+                continue;
+            }
+        };
 
-    for (option_name, raw_value) in raw_options.into_iter() {
-        let (option_name, option_value) = parse_option(option_name, raw_value, option_descriptor_lookup)?;
-        option_value_lookup.insert(option_name, option_value);
+        match (option_value_lookup.get_mut(option_name), option_value) {
+            (Some(option::Value::List { value }), option::Value::List { value: additional }) => value.extend(additional),
+            (Some(option::Value::Int { value }), option::Value::Int { value: additional }) if is_count => *value += additional,
+            (Some(_), _) => diagnostics.report(ParserError::DuplicateOption {
+                option: raw_option.spelling,
+                index: Some(raw_option.index),
+                group_path: Vec::new(),
+            })?,
+            (None, option_value) => {
+                option_value_lookup.insert(option_name, option_value, option::Source::CommandLine { spelling: raw_option.spelling });
+            }
+        }
     }
 
     Ok(option_value_lookup)
 }
 
-/// Parse the passed option (name and raw value).
-fn parse_option<'a>(name: &str, raw_value: &str, option_descriptor_lookup: &HashMap<&String, &'a option::Descriptor>) -> Result<(&'a String, option::Value)> {
-    let option_descriptor = get_option_descriptor_for_name(name, option_descriptor_lookup)?;
+/// Parse the value of the passed raw option.
+fn parse_option(raw_option: &RawOption) -> Result<option::Value> {
+    let option_type = raw_option.descriptor.value_type();
 
-    Ok((option_descriptor.name(), match option::Value::parse(option_descriptor.value_type(), raw_value) {
-        Ok(v) => v,
-        Err(_) => return Err(ParserError {
-            message: format!("Expected value '{}' of option '--{}' to be of type '{}'", raw_value, name, option_descriptor.value_type())
+    match option::Value::parse(option_type, raw_option.value) {
+        Ok(v) => Ok(v),
+        Err(_) => Err(ParserError::InvalidValue {
+            option: raw_option.descriptor.name().clone(),
+            value: String::from(raw_option.value),
+            source: Box::new(option::Source::CommandLine { spelling: raw_option.spelling.clone() }),
+            index: Some(raw_option.index),
+            expected: Box::new(option_type.clone()),
+            group_path: Vec::new(),
         })
-    }))
+    }
+}
+
+/// Add all missing options in the lookup bound to a set environment variable.
+fn fill_environment_options(
+    option_value_lookup: &mut option::Values,
+    anticipated_options: &OptionDescriptors,
+    environment: &dyn Environment,
+    diagnostics: &mut Diagnostics,
+) -> Result<()> {
+    for (option_name, descriptor) in anticipated_options {
+        if option_value_lookup.contains(option_name) {
+            continue;
+        }
+
+        let variable = match descriptor.env() {
+            Some(variable) => variable,
+            None => continue,
+        };
+
+        if let Some(raw_value) = environment.var(variable) {
+            let value = match option::Value::parse(descriptor.value_type(), &raw_value) {
+                Ok(v) => v,
+                Err(_) => {
+                    diagnostics.report(ParserError::InvalidValue {
+                        option: option_name.to_string(),
+                        value: raw_value,
+                        source: Box::new(option::Source::Environment { variable: variable.clone() }),
+                        index: None,
+                        expected: Box::new(descriptor.value_type().clone()),
+                        group_path: Vec::new(),
+                    })?;
+                    continue;
+                }
+            };
+
+            option_value_lookup.insert(option_name, value, option::Source::Environment { variable: variable.clone() });
+        }
+    }
+
+    Ok(())
+}
+
+/// Add all missing options in the lookup specified in the passed configuration files.
+/// Entries apply when their section matches the group path (or a prefix of it), where entries of
+/// more specific sections take precedence over less specific ones.
+fn fill_config_options(
+    option_value_lookup: &mut option::Values,
+    anticipated_options: &OptionDescriptors,
+    group_path: &[String],
+    config_files: &[PathBuf],
+    diagnostics: &mut Diagnostics,
+) -> Result<()> {
+    let mut config_values = option::Values::default();
+
+    for path in config_files {
+        let config_file = match ConfigFile::read(path) {
+            Ok(Some(v)) => v,
+            Ok(None) => continue,
+            Err(e) => {
+                diagnostics.report(e)?;
+                continue;
+            }
+        };
+
+        let mut entries: Vec<_> = config_file.entries.iter()
+            .filter(|e| group_path.starts_with(&e.section))
+            .collect();
+        entries.sort_by_key(|e| e.section.len()); // Stable, thus later entries of the same section win
+
+        for entry in entries {
+            let descriptor = match anticipated_options.get(&entry.key) {
+                Some(v) if !option_value_lookup.contains(&entry.key) => v,
+                _ => continue,
+            };
+
+            let value = match parse_config_value(&entry.value, descriptor.value_type()) {
+                Ok(v) => v,
+                Err(raw_value) => {
+                    diagnostics.report(ParserError::InvalidValue {
+                        option: entry.key.clone(),
+                        value: raw_value,
+                        source: Box::new(option::Source::ConfigFile { path: config_file.path.clone(), line: entry.line }),
+                        index: None,
+                        expected: Box::new(descriptor.value_type().clone()),
+                        group_path: Vec::new(),
+                    })?;
+                    continue;
+                }
+            };
+
+            config_values.insert(&entry.key, value, option::Source::ConfigFile {
+                path: config_file.path.clone(),
+                line: entry.line,
+            });
+        }
+    }
+
+    for (option_name, value, source) in config_values.iter() {
+        option_value_lookup.insert(option_name, value.clone(), source.clone());
+    }
+
+    Ok(())
+}
+
+/// Parse the raw value of a configuration file entry.
+/// Returns the offending raw value on failure.
+fn parse_config_value(raw_value: &RawValue, option_type: &option::Type) -> result::Result<option::Value, String> {
+    match raw_value {
+        RawValue::Single(raw) => option::Value::parse(option_type, raw).map_err(|_| raw.clone()),
+        RawValue::List(raw_values) if option_type.is_list() => {
+            let mut values = Vec::with_capacity(raw_values.len());
+            for raw in raw_values {
+                if let Ok(option::Value::List { value }) = option::Value::parse(option_type, raw) {
+                    values.extend(value);
+                } else {
+                    return Err(raw.clone());
+                }
+            }
+
+            Ok(option::Value::List { value: values })
+        }
+        RawValue::List(raw_values) => Err(format!("[{}]", raw_values.join(", "))),
+    }
 }
 
 /// Add all missing options in the lookup with default values.
-fn fill_default_options<'a>(option_value_lookup: &mut HashMap<&'a str, option::Value>, anticipated_options: &'a HashMap<Rc<String>, Rc<option::Descriptor>>) {
+/// Options without default value are left out.
+fn fill_default_options(option_value_lookup: &mut option::Values, anticipated_options: &OptionDescriptors) {
     for (option_name, descriptor) in anticipated_options {
-        if !option_value_lookup.contains_key(option_name as &str) {
-            option_value_lookup.insert(option_name, option::Value::from_default(descriptor.value_type()));
+        if descriptor.has_default() && !option_value_lookup.contains(option_name) {
+            option_value_lookup.insert(option_name, option::Value::from_default(descriptor.value_type()), option::Source::Default);
         }
     }
 }
 
+/// Check that all required options have been specified.
+fn check_required_options(option_value_lookup: &option::Values, anticipated_options: &OptionDescriptors) -> Result<()> {
+    let mut missing: Vec<String> = anticipated_options.values()
+        .filter(|d| d.is_required() && !option_value_lookup.contains(d.name()))
+        .map(|d| d.name().clone())
+        .collect();
+
+    if missing.is_empty() {
+        return Ok(());
+    }
+
+    missing.sort();
+    Err(ParserError::MissingRequiredOptions {
+        options: missing,
+        group_path: Vec::new(),
+    })
+}
+
 /// Parse the passed raw command line arguments to their actual argument values.
-fn parse_arguments(descriptors: &Vec<arg::Descriptor>, raw_arguments: Vec<&str>) -> Result<Vec<arg::Value>> {
-    if raw_arguments.len() != descriptors.len() {
-        return Err(ParserError {
-            message: format!("Expected to have {} arguments but got {}", descriptors.len(), raw_arguments.len())
+/// Raw arguments are distributed from left to right, each descriptor taking its minimum number
+/// of values first, while the remaining ones are assigned to the first descriptors able to take more.
+/// Invalid argument values are reported to the passed diagnostics, while a wrong number of arguments fails.
+fn parse_arguments(descriptors: &[arg::Descriptor], raw_arguments: &[RawArgument], diagnostics: &mut Diagnostics) -> Result<Vec<arg::Value>> {
+    let min: usize = descriptors.iter().map(|d| d.arity().min()).sum();
+    let max: Option<usize> = descriptors.iter().map(|d| d.arity().max()).sum();
+
+    if raw_arguments.len() < min || max.is_some_and(|max| raw_arguments.len() > max) {
+        return Err(ParserError::WrongArgumentCount {
+            expected: arg::Arity::Range { min, max },
+            actual: raw_arguments.len(),
+            group_path: Vec::new(),
         });
     }
 
-    let mut argument_values = Vec::with_capacity(raw_arguments.len());
-    for i in 0..raw_arguments.len() {
-        let desc = &descriptors[i];
-        let arg = raw_arguments[i];
+    let mut remaining = raw_arguments.len() - min;
+    let mut pos = 0;
 
-        // Check if argument is parsable using the argument descriptor information
-        let value = match arg::Value::parse(desc.value_type(), arg) {
-            Ok(v) => v,
-            Err(_) => return Err(ParserError {
-                message: format!("Expected argument '{}' at position {} to be of type '{}'", arg, i + 1, desc.value_type())
-            })
+    let mut argument_values = Vec::with_capacity(descriptors.len());
+    for desc in descriptors {
+        let arity = desc.arity();
+        let additional = match arity.max() {
+            Some(max) => remaining.min(max - arity.min()),
+            None => remaining,
         };
+        remaining -= additional;
+
+        let count = arity.min() + additional;
+        let mut values = Vec::with_capacity(count);
+        for (i, arg) in raw_arguments[pos..pos + count].iter().enumerate() {
+            // Check if argument is parsable using the argument descriptor information
+            let value = match arg::Value::parse(desc.value_type(), arg.value) {
+                Ok(v) => v,
+                Err(_) => {
+                    diagnostics.report(ParserError::InvalidArgument {
+                        value: String::from(arg.value),
+                        position: pos + i + 1,
+                        index: Some(arg.index),
+                        expected: desc.value_type().clone(),
+                        group_path: Vec::new(),
+                    })?;
+                    continue;
+                }
+            };
+
+            values.push(value);
+        }
+        pos += count;
 
-        argument_values.push(value);
+        if values.len() < count {
+            continue; // Invalid values have already been reported
+        }
+
+        argument_values.push(match arity {
+            arg::Arity::Single => values.remove(0),
+            arg::Arity::Optional { default } => values.pop().unwrap_or_else(|| default.clone()),
+            arg::Arity::Range { min: _, max: _ } => arg::Value::List { value: values },
+        });
     }
 
     Ok(argument_values)
 }
 
+/// Get an error explaining the passed raw positional arguments of the passed group as mistyped sub-command.
+/// This is the case when the first argument resembles the name or alias of a child group, or when
+/// the group takes no arguments but has children at all.
+fn unknown_child_error<C, F: ?Sized>(group: &Group<C, F>, raw_arguments: &[RawArgument]) -> Option<ParserError> {
+    let arg = raw_arguments.first()?;
+    if group.get_children().is_empty() {
+        return None;
+    }
+
+    let suggestions: Vec<String> = suggestion::similar_names(arg.value, group.get_child_names_known().into_iter().map(|name| name.as_str()))
+        .into_iter()
+        .map(String::from)
+        .collect();
+    if suggestions.is_empty() && !group.get_arguments().is_empty() {
+        return None;
+    }
+
+    Some(ParserError::UnknownSubCommand {
+        token: String::from(arg.value),
+        index: Some(arg.index),
+        suggestions,
+        group_path: Vec::new(),
+    })
+}
+
+/// Get the argument descriptor most likely responsible for the raw argument at the passed position.
+fn get_argument_descriptor_at(descriptors: &[arg::Descriptor], position: usize) -> Option<&arg::Descriptor> {
+    let mut end = 0;
+    for desc in descriptors {
+        match desc.arity().max() {
+            Some(max) => end += max,
+            None => return Some(desc), // Takes all remaining arguments
+        }
+
+        if position < end {
+            return Some(desc);
+        }
+    }
+
+    None
+}
+
 /// Show help for the passed group configuration.
-fn show_help(group: &Group, option_descriptors: &HashMap<Rc<String>, Rc<option::Descriptor>>, arg_descriptors: &Vec<arg::Descriptor>, help_printer: Option<Box<dyn HelpPrinter>>) {
+pub(crate) fn show_help<C, F: ?Sized>(group: &Group<C, F>, option_descriptors: &OptionDescriptors, arg_descriptors: &[arg::Descriptor], help_printer: Option<&dyn HelpPrinter<C, F>>) {
+    let (subcommand_entries, option_entries) = help_entries(group, option_descriptors);
+
+    match help_printer {
+        Some(v) => v.print(group, &subcommand_entries, &option_entries, arg_descriptors),
+        None => DefaultHelpPrinter {}.print(group, &subcommand_entries, &option_entries, arg_descriptors),
+    }
+}
+
+/// Write the help documentation of the passed group to the passed writer.
+pub(crate) fn write_help<C, F: ?Sized>(group: &Group<C, F>, option_descriptors: &OptionDescriptors, arg_descriptors: &[arg::Descriptor], help_printer: Option<&dyn HelpPrinter<C, F>>, out: &mut dyn io::Write) -> io::Result<()> {
+    let (subcommand_entries, option_entries) = help_entries(group, option_descriptors);
+
+    match help_printer {
+        Some(v) => v.write(out, group, &subcommand_entries, &option_entries, arg_descriptors),
+        None => DefaultHelpPrinter {}.write(out, group, &subcommand_entries, &option_entries, arg_descriptors),
+    }
+}
+
+/// Collect the sub-command and option entries of the help documentation (sorted by name).
+fn help_entries<'a, C, F: ?Sized>(group: &'a Group<C, F>, option_descriptors: &'a OptionDescriptors) -> (Vec<SubcommandEntry<'a, C, F>>, Vec<OptionEntry<'a>>) {
     // Collect subcommand entries
     let mut subcommand_entries = Vec::with_capacity(group.get_children().len());
     for (group_name, group) in group.get_children() {
@@ -288,8 +1443,5 @@ fn show_help(group: &Group, option_descriptors: &HashMap<Rc<String>, Rc<option::
     }
     option_entries.sort_by(|a, b| a.key.cmp(b.key));
 
-    match help_printer {
-        Some(v) => v.print(group, &subcommand_entries, &option_entries, arg_descriptors),
-        None => DefaultHelpPrinter {}.print(group, &subcommand_entries, &option_entries, arg_descriptors),
-    }
+    (subcommand_entries, option_entries)
 }
@@ -0,0 +1,175 @@
+use std::io::{self, BufRead, Write};
+use crate::{CommandResult, Consumer, Group, RunError, arg, option};
+use std::sync::Arc;
+use crate::parser::{ConsumerKind, ParseOptions, Parser};
+use crate::response_file;
+
+static DEFAULT_PROMPT: &str = "> ";
+static HELP_COMMAND: &str = "help";
+static HISTORY_COMMAND: &str = "history";
+static EXIT_COMMANDS: [&str; 2] = ["exit", "quit"];
+static HELP_OPTION: &str = "--help";
+
+/// Placeholder for the program name expected by the parser as first argument.
+static PROGRAM_NAME: &str = "";
+
+/// Interactive shell reading commands line by line and parsing each of them against the same
+/// group (without program name), for example to offer `mytool shell`.
+/// Lines are split into arguments at whitespace, where arguments may be quoted using `"` or `'`
+/// and a `\` escapes the following char.
+/// Errors are written to the output without terminating the shell.
+/// The group is either owned by the shell (see `Repl::new`) or borrowed for the lifetime `'g`
+/// (see `Repl::borrowed`).
+/// Besides the sub-commands of the group the following built-in commands are available
+/// (unless shadowed by a sub-command of the same name):
+/// - `help [sub-command...]` writes the help documentation of the group or passed sub-command,
+/// - `history` lists the lines entered so far,
+/// - `exit` or `quit` terminates the shell (as does the end of the input).
+pub struct Repl<'g, C = (), F: ?Sized + ConsumerKind<C> = Consumer<C>> {
+    /// Parser for the lines entered.
+    parser: Parser<'g, C, F>,
+
+    /// Prompt written before reading a line.
+    prompt: String,
+
+    /// Lines entered so far (excluding empty lines).
+    history: Vec<String>,
+}
+
+/// Command built into the shell.
+enum Builtin {
+    Help,
+    History,
+    Exit,
+}
+
+impl<F> Repl<'_, (), F> where F: ?Sized + ConsumerKind<()> + Fn(&mut (), &arg::Values, &option::Values) -> CommandResult {
+    /// Read and execute lines from the passed input until it ends or the shell is exited.
+    pub fn run<R: BufRead, W: Write>(&mut self, input: R, output: W) -> io::Result<()> {
+        self.run_with(&mut (), input, output)
+    }
+}
+
+impl<'g, C, F> Repl<'g, C, F> where F: ?Sized + ConsumerKind<C> + Fn(&mut C, &arg::Values, &option::Values) -> CommandResult {
+    /// Create a shell for the passed group (or group shared via `Arc`) using the passed options.
+    pub fn new<G: Into<Arc<Group<C, F>>>>(group: G, options: ParseOptions<C, F>) -> Self {
+        Repl::with_parser(Parser::new(group, options))
+    }
+
+    /// Create a shell for the passed borrowed group using the passed options.
+    pub fn borrowed(group: &'g Group<C, F>, options: ParseOptions<C, F>) -> Self {
+        Repl::with_parser(Parser::borrowed(group, options))
+    }
+
+    /// Create a shell parsing the lines entered using the passed parser.
+    fn with_parser(parser: Parser<'g, C, F>) -> Self {
+        Repl {
+            parser,
+            prompt: String::from(DEFAULT_PROMPT),
+            history: Vec::new(),
+        }
+    }
+
+    /// Set the prompt written before reading a line (`> ` by default).
+    pub fn with_prompt(mut self, prompt: &str) -> Self {
+        self.prompt = String::from(prompt);
+        self
+    }
+
+    /// Get the lines entered so far (excluding empty lines).
+    pub fn history(&self) -> &[String] {
+        &self.history
+    }
+
+    /// Read and execute lines from the passed input until it ends or the shell is exited,
+    /// handing the passed context to the consumers of the entered commands.
+    pub fn run_with<R: BufRead, W: Write>(&mut self, context: &mut C, mut input: R, mut output: W) -> io::Result<()> {
+        loop {
+            write!(output, "{}", self.prompt)?;
+            output.flush()?;
+
+            let mut line = String::new();
+            if input.read_line(&mut line)? == 0 {
+                return Ok(()); // End of input
+            }
+
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            self.history.push(String::from(line));
+
+            if !self.execute(context, line, &mut output)? {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Execute the passed line.
+    /// Returns whether to continue reading lines.
+    fn execute(&self, context: &mut C, line: &str, output: &mut dyn Write) -> io::Result<bool> {
+        let words = match response_file::split(line) {
+            Ok(words) => words,
+            Err(message) => {
+                writeln!(output, "{}", message)?;
+                return Ok(true);
+            }
+        };
+
+        let mut args: Vec<&str> = Vec::with_capacity(words.len() + 2);
+        args.push(PROGRAM_NAME);
+        args.extend(words.iter().map(String::as_str));
+
+        match words.first().and_then(|name| self.builtin(name)) {
+            Some(Builtin::Exit) => return Ok(false),
+            Some(Builtin::History) => {
+                for (i, entry) in self.history.iter().enumerate() {
+                    writeln!(output, "{:>4}  {}", i + 1, entry)?;
+                }
+                return Ok(true);
+            }
+            Some(Builtin::Help) => {
+                // Request the help documentation of the passed sub-command instead
+                args.remove(1);
+                args.push(HELP_OPTION);
+            }
+            None => {}
+        }
+
+        let error = match self.parser.get_matches_from(&args[..]) {
+            Ok(matches) if matches.is_help_requested() => {
+                matches.write_help(self.parser.help_printer(), output)?;
+                return Ok(true);
+            }
+            Ok(matches) => match matches.run_with(context) {
+                Ok(_) => return Ok(true),
+                Err(e) => RunError::Command(e),
+            },
+            Err(e) => RunError::Parser(e),
+        };
+
+        match &error {
+            RunError::Command(command_error) if command_error.error().is_none() => {} // Nothing to report
+            _ => writeln!(output, "{}", error)?,
+        }
+
+        Ok(true)
+    }
+
+    /// Get the built-in command for the passed name (unless shadowed by a sub-command).
+    fn builtin(&self, name: &str) -> Option<Builtin> {
+        if self.parser.group().get_child_known_for(name).is_some() {
+            return None;
+        }
+
+        if name == HELP_COMMAND {
+            Some(Builtin::Help)
+        } else if name == HISTORY_COMMAND {
+            Some(Builtin::History)
+        } else if EXIT_COMMANDS.contains(&name) {
+            Some(Builtin::Exit)
+        } else {
+            None
+        }
+    }
+}
@@ -0,0 +1,138 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use crate::error::ParserError;
+use crate::parser::Result;
+
+static RESPONSE_FILE_PREFIX: char = '@';
+static END_OF_OPTIONS: &str = "--";
+
+/// Expand `@path` arguments to the arguments contained in the response file at that path.
+/// Arguments in response files are separated by whitespace (including newlines) and may be quoted
+/// using `"` or `'` to contain whitespace.
+/// Within double quotes and unquoted arguments a `\` escapes the following char.
+/// Response files may reference further response files, where relative paths are resolved
+/// relative to the directory of the referencing file.
+/// An argument starting with `@@` is passed literally with the leading `@` removed.
+/// The program name (first argument) and arguments following the end-of-options terminator `--`
+/// are never expanded.
+pub(crate) fn expand(args: &[&str]) -> Result<Vec<String>> {
+    let mut expanded = Vec::with_capacity(args.len());
+    let mut stack = Vec::new();
+
+    let mut args = args.iter();
+    if let Some(program) = args.next() {
+        expanded.push(String::from(*program));
+    }
+
+    while let Some(arg) = args.next() {
+        if *arg == END_OF_OPTIONS {
+            expanded.push(String::from(*arg));
+            expanded.extend(args.map(|s| String::from(*s)));
+            break;
+        }
+
+        expand_arg(arg, None, &mut stack, &mut expanded)?;
+    }
+
+    Ok(expanded)
+}
+
+/// Expand the passed argument (if referencing a response file) into the passed result.
+/// The stack holds the response files currently being expanded to detect cycles.
+fn expand_arg(arg: &str, base_dir: Option<&Path>, stack: &mut Vec<PathBuf>, result: &mut Vec<String>) -> Result<()> {
+    let path = match arg.strip_prefix(RESPONSE_FILE_PREFIX) {
+        Some(rest) if rest.starts_with(RESPONSE_FILE_PREFIX) => {
+            result.push(String::from(rest)); // Escaped literal argument starting with '@'
+            return Ok(());
+        }
+        Some(rest) if !rest.is_empty() => match base_dir {
+            Some(dir) => dir.join(rest),
+            None => PathBuf::from(rest),
+        },
+        _ => {
+            result.push(String::from(arg));
+            return Ok(());
+        }
+    };
+
+    let canonical_path = fs::canonicalize(&path).map_err(|e| ParserError::ResponseFile {
+        path: path.clone(),
+        message: format!("could not be read: {}", e),
+    })?;
+    if stack.contains(&canonical_path) {
+        return Err(ParserError::ResponseFile {
+            path,
+            message: String::from("references itself recursively"),
+        });
+    }
+
+    let content = fs::read_to_string(&canonical_path).map_err(|e| ParserError::ResponseFile {
+        path: path.clone(),
+        message: format!("could not be read: {}", e),
+    })?;
+    let args = split(&content).map_err(|message| ParserError::ResponseFile {
+        path: path.clone(),
+        message: format!("could not be parsed: {}", message),
+    })?;
+
+    let base_dir = canonical_path.parent().map(Path::to_path_buf);
+    stack.push(canonical_path);
+    for arg in args {
+        expand_arg(&arg, base_dir.as_deref(), stack, result)?;
+    }
+    stack.pop();
+
+    Ok(())
+}
+
+/// Split the passed content into arguments separated by whitespace respecting quotes.
+pub(crate) fn split(content: &str) -> std::result::Result<Vec<String>, String> {
+    let mut args = Vec::new();
+    let mut current: Option<String> = None;
+    let mut chars = content.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => {
+                if let Some(arg) = current.take() {
+                    args.push(arg);
+                }
+            }
+            '\'' => {
+                let arg = current.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => arg.push(c),
+                        None => return Err(String::from("Missing closing quote (')")),
+                    }
+                }
+            }
+            '"' => {
+                let arg = current.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(c) => arg.push(c),
+                            None => return Err(String::from("Missing char to escape after '\\'")),
+                        },
+                        Some(c) => arg.push(c),
+                        None => return Err(String::from("Missing closing quote (\")")),
+                    }
+                }
+            }
+            '\\' => match chars.next() {
+                Some(c) => current.get_or_insert_with(String::new).push(c),
+                None => return Err(String::from("Missing char to escape after '\\'")),
+            },
+            c => current.get_or_insert_with(String::new).push(c),
+        }
+    }
+
+    if let Some(arg) = current {
+        args.push(arg);
+    }
+
+    Ok(args)
+}
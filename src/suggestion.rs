@@ -0,0 +1,57 @@
+/// Get the known names most similar to the passed (probably mistyped) name.
+/// Only names within a small edit distance relative to the length of the passed name are
+/// considered, and of those only the ones with the smallest distance are returned (sorted).
+pub(crate) fn similar_names<'a, I>(name: &str, known_names: I) -> Vec<&'a str>
+    where I: IntoIterator<Item=&'a str> {
+    let max_distance = (name.chars().count() / 3).max(1);
+
+    let mut best_distance = max_distance + 1;
+    let mut best_names: Vec<&'a str> = Vec::new();
+    for known_name in known_names {
+        let distance = edit_distance(name, known_name);
+        if distance < best_distance {
+            best_distance = distance;
+            best_names.clear();
+        }
+        if distance == best_distance {
+            best_names.push(known_name);
+        }
+    }
+
+    best_names.sort_unstable();
+    best_names.dedup();
+    best_names
+}
+
+/// Compute the optimal string alignment distance (Levenshtein distance additionally counting
+/// the transposition of two adjacent characters as a single edit) between the passed strings.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+
+    // Rows of the distance matrix for the previous two and the current character of `a`
+    let mut before_previous: Vec<usize> = vec![0; b.len() + 1];
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current: Vec<usize> = vec![0; b.len() + 1];
+
+    for i in 1..=a.len() {
+        current[0] = i;
+
+        for j in 1..=b.len() {
+            let cost = if a[i - 1] == b[j - 1] { 0 } else { 1 };
+
+            current[j] = (previous[j] + 1)
+                .min(current[j - 1] + 1)
+                .min(previous[j - 1] + cost);
+
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                current[j] = current[j].min(before_previous[j - 2] + 1);
+            }
+        }
+
+        std::mem::swap(&mut before_previous, &mut previous);
+        std::mem::swap(&mut previous, &mut current);
+    }
+
+    previous[b.len()]
+}